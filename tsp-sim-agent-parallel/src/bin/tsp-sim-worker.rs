@@ -0,0 +1,48 @@
+// Worker process for a `DistributedSimulation`: connects to the coordinator and runs a genetic
+// simulation on the locations it sends.
+//
+// usage: tsp-sim-worker <coordinator address> [population size]
+
+use std::env;
+use std::process::ExitCode;
+use std::str::FromStr;
+use tsp_sim_agent::GeneticSimulation;
+use tsp_sim_agent_parallel::run_worker;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+    let Some(address) = args.get(1) else {
+        eprintln!("usage: tsp-sim-worker <coordinator address> [population size]");
+        return ExitCode::FAILURE;
+    };
+    let population_size = match args.get(2).map(|population| usize::from_str(population)) {
+        None => 200,
+        Some(Ok(population_size)) => population_size,
+        Some(Err(error)) => {
+            eprintln!("invalid population size: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = run_worker(address.as_str(), |job| {
+        eprintln!(
+            "worker {}: solving {} locations",
+            job.worker,
+            job.locations.len()
+        );
+        GeneticSimulation {
+            population_size,
+            ..GeneticSimulation::new(job.locations.clone())
+        }
+    });
+    match result {
+        Ok(route) => {
+            eprintln!("finished with distance {:.3}", route.distance);
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("worker failed: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::iter;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+use tsp_sim_agent::{Route, SimulationProgress};
+
+// How long the coordinators wait for events before checking their control and stop condition
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Waits up to the poll interval for an event and takes it with every other pending event; `None`
+// once all the senders are gone
+pub(crate) fn receive_events<T>(receiver: &Receiver<T>) -> Option<Vec<T>> {
+    match receiver.recv_timeout(POLL_INTERVAL) {
+        Ok(event) => Some(iter::once(event).chain(receiver.try_iter()).collect()),
+        Err(RecvTimeoutError::Timeout) => Some(vec![]),
+        Err(RecvTimeoutError::Disconnected) => None,
+    }
+}
+
+// Iterations and evaluations reported by every worker, and the best route any of them found, as
+// the totals a coordinator reports and checks its stop condition on
+pub(crate) struct Progress {
+    iterations: Vec<usize>,
+    evaluations: Vec<usize>,
+    champion: Route,
+    // total iterations when the champion was found
+    champion_total_iterations: usize,
+}
+
+impl Progress {
+    pub(crate) fn new() -> Self {
+        Self {
+            iterations: vec![],
+            evaluations: vec![],
+            champion: Route {
+                locations: vec![],
+                distance: f64::MAX,
+            },
+            champion_total_iterations: 0,
+        }
+    }
+
+    // Records the iterations of the worker, returning the total
+    pub(crate) fn set_iterations(&mut self, worker: usize, iterations: usize) -> usize {
+        set(&mut self.iterations, worker, iterations);
+        self.total_iterations()
+    }
+
+    // Records the evaluations of the worker, returning the total
+    pub(crate) fn set_evaluations(&mut self, worker: usize, evaluations: usize) -> usize {
+        set(&mut self.evaluations, worker, evaluations);
+        self.total_evaluations()
+    }
+
+    pub(crate) fn total_iterations(&self) -> usize {
+        self.iterations.iter().sum()
+    }
+
+    pub(crate) fn total_evaluations(&self) -> usize {
+        self.evaluations.iter().sum()
+    }
+
+    // Takes the route as the champion if it is the shortest so far
+    pub(crate) fn offer_champion(&mut self, route: Route) -> bool {
+        if route.distance < self.champion.distance {
+            self.champion_total_iterations = self.total_iterations();
+            self.champion = route;
+            true
+        } else {
+            false
+        }
+    }
+
+    // The champion, once a worker found one
+    pub(crate) fn champion(&self) -> Option<&Route> {
+        (!self.champion.locations.is_empty()).then_some(&self.champion)
+    }
+
+    pub(crate) fn simulation_progress(&self) -> SimulationProgress {
+        let total_iterations = self.total_iterations();
+        SimulationProgress {
+            iteration: total_iterations,
+            champion_iterations: total_iterations - self.champion_total_iterations,
+            evaluations: self.total_evaluations(),
+            champion_distance: self.champion.distance,
+        }
+    }
+
+    // The shortest of the final routes of the workers and the champion they reported
+    pub(crate) fn best_route(self, mut routes: Vec<Route>) -> Option<Route> {
+        if !self.champion.locations.is_empty() {
+            routes.push(self.champion);
+        }
+        routes
+            .into_iter()
+            .min_by(|r1, r2| r1.distance.total_cmp(&r2.distance))
+    }
+}
+
+// Workers can be added while running, so the counts grow as they report
+fn set(counts: &mut Vec<usize>, worker: usize, count: usize) {
+    if counts.len() <= worker {
+        counts.resize(worker + 1, 0);
+    }
+    counts[worker] = count;
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn route(distance: f64) -> Route {
+        Route {
+            locations: vec![],
+            distance,
+        }
+    }
+
+    #[test]
+    fn test_progress_totals() {
+        let mut progress = Progress::new();
+        assert_eq!(progress.set_iterations(1, 100), 100);
+        assert_eq!(progress.set_iterations(0, 50), 150);
+        assert_eq!(progress.set_iterations(1, 200), 250);
+        assert_eq!(progress.set_evaluations(0, 1000), 1000);
+
+        assert!(progress.offer_champion(route(10.0)));
+        assert!(!progress.offer_champion(route(12.0)));
+        progress.set_iterations(0, 80);
+        let simulation_progress = progress.simulation_progress();
+        assert_eq!(simulation_progress.iteration, 280);
+        assert_eq!(simulation_progress.champion_iterations, 30);
+        assert_eq!(simulation_progress.champion_distance, 10.0);
+
+        let best = progress.best_route(vec![route(11.0), route(9.0)]);
+        assert_eq!(best.map(|route| route.distance), Some(9.0));
+    }
+
+    #[test]
+    fn test_receive_events() {
+        let (sender, receiver) = mpsc::channel();
+        assert_eq!(receive_events(&receiver), Some(vec![]));
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        assert_eq!(receive_events(&receiver), Some(vec![1, 2]));
+        drop(sender);
+        assert_eq!(receive_events(&receiver), None);
+    }
+}
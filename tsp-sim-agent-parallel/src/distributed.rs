@@ -0,0 +1,525 @@
+use crate::coordinator::{self, Progress, POLL_INTERVAL};
+use crate::island::{IslandModel, MigrationTopology};
+use rand::prelude::{SeedableRng, SliceRandom};
+use rand_chacha::ChaCha8Rng;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::mem;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tsp_sim_agent::{
+    Location, Migration, Route, Simulation, SimulationControl, SimulationError, SimulationEvent,
+    StopCondition, StopConditionTracker,
+};
+
+// How long the coordinator waits for the final routes of the workers once they are told to stop
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Work sent by the coordinator to a worker once it connects.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct WorkerJob {
+    pub worker: usize,
+    pub locations: Vec<Location>,
+    /// Iterations between migrations, or 0 if the workers don't exchange routes.
+    pub migration_interval: usize,
+    /// Number of routes sent on each migration.
+    pub migration_size: usize,
+}
+
+/// Messages sent by the coordinator to the workers, as JSON lines.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum CoordinatorMessage {
+    Job(WorkerJob),
+    Immigrants(Vec<Route>),
+    Pause,
+    Resume,
+    Stop,
+}
+
+/// Messages sent by a worker to the coordinator, as JSON lines.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum WorkerMessage {
+    Started,
+    Iteration(usize),
+    Evaluations(usize),
+    // (route, iteration)
+    NewChampion(Route, usize),
+    // (mean distance, iteration)
+    MeanDistance(f64, usize),
+    Emigrants(Vec<Route>),
+    Finished(Route),
+    Failed(String),
+}
+
+fn network_error(error: io::Error) -> SimulationError {
+    SimulationError::Network(error.to_string())
+}
+
+fn send_message<M: Serialize>(stream: &mut TcpStream, message: &M) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)
+}
+
+// Sends the message to the workers that are not done; those that just finished may have closed
+// their connection
+fn broadcast(writers: &mut [TcpStream], done: &[bool], message: &CoordinatorMessage) {
+    for (writer, _) in writers.iter_mut().zip(done).filter(|(_, done)| !**done) {
+        let _ = send_message(writer, message);
+    }
+}
+
+fn receive_messages<M: DeserializeOwned>(stream: TcpStream) -> impl Iterator<Item = io::Result<M>> {
+    BufReader::new(stream)
+        .lines()
+        .map(|line| line.and_then(|line| Ok(serde_json::from_str(&line)?)))
+}
+
+/// Coordinates workers running in other processes, possibly on other machines, that connect to it
+/// over TCP. Every worker runs its own simulation and streams its progress back; the coordinator
+/// aggregates it like a `ParallelSimulation` and forwards migrating routes between the workers.
+#[derive(Debug)]
+pub struct DistributedSimulation {
+    listener: TcpListener,
+    pub locations: Vec<Location>,
+    /// Workers that must connect before the simulation starts.
+    pub workers: usize,
+    /// How long to wait for all the workers to connect.
+    pub connect_timeout: Duration,
+    /// Exchanges routes between the workers instead of running them independently.
+    pub island_model: Option<IslandModel>,
+    /// Evaluated on the totals aggregated over all workers.
+    pub stop_condition: Option<StopCondition>,
+    /// Seeds the destinations of `MigrationTopology::Random`; the workers seed their own
+    /// simulations.
+    pub seed: Option<u64>,
+}
+
+impl Simulation for DistributedSimulation {
+    fn run<F>(
+        &self,
+        control: &SimulationControl,
+        simulation_event_callback: F,
+    ) -> Result<Route, SimulationError>
+    where
+        F: Fn(SimulationEvent),
+    {
+        self.validate()?;
+
+        let streams = self.accept_workers(control)?;
+        let workers = streams.len();
+
+        // all workers' messages, tagged with the worker index, are received on the same channel
+        let (message_sender, message_receiver) =
+            mpsc::channel::<(usize, Result<WorkerMessage, String>)>();
+        let mut writers: Vec<TcpStream> = Vec::with_capacity(workers);
+        for (worker, mut stream) in streams.into_iter().enumerate() {
+            let job = WorkerJob {
+                worker,
+                locations: self.locations.clone(),
+                migration_interval: self.island_model.as_ref().map_or(0, |model| model.interval),
+                migration_size: self.island_model.as_ref().map_or(0, |model| model.size),
+            };
+            send_message(&mut stream, &CoordinatorMessage::Job(job)).map_err(network_error)?;
+
+            let reader = stream.try_clone().map_err(network_error)?;
+            let message_sender = message_sender.clone();
+            thread::spawn(move || {
+                for message in receive_messages::<WorkerMessage>(reader) {
+                    let failed = message.is_err();
+                    let message = message.map_err(|error| error.to_string());
+                    if message_sender.send((worker, message)).is_err() || failed {
+                        return;
+                    }
+                }
+                let _ = message_sender.send((worker, Err("connection closed".to_owned())));
+            });
+            writers.push(stream);
+        }
+        drop(message_sender);
+
+        let mut started: Vec<bool> = vec![false; workers];
+        let mut done: Vec<bool> = vec![false; workers];
+        let mut mean_distances: Vec<Option<f64>> = vec![None; workers];
+        let mut routes: Vec<Route> = vec![];
+        let mut last_failure: Option<String> = None;
+        let mut progress = Progress::new();
+        let mut stop_condition = self.stop_condition.as_ref().map(StopConditionTracker::new);
+        let mut stop_deadline: Option<Instant> = None;
+        let mut rng = match self.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_entropy(),
+        };
+
+        while let Some(messages) = coordinator::receive_events(&message_receiver) {
+            for (worker, message) in messages {
+                if done[worker] {
+                    continue;
+                }
+                match message {
+                    Ok(WorkerMessage::Started) => {
+                        started[worker] = true;
+                        if started.iter().all(|started| *started) {
+                            simulation_event_callback(SimulationEvent::Started);
+                        }
+                    }
+                    Ok(WorkerMessage::Iteration(iteration)) => {
+                        let iterations = progress.set_iterations(worker, iteration);
+                        simulation_event_callback(SimulationEvent::Iteration(iterations));
+                    }
+                    Ok(WorkerMessage::Evaluations(evaluations)) => {
+                        let evaluations = progress.set_evaluations(worker, evaluations);
+                        simulation_event_callback(SimulationEvent::Evaluations(evaluations));
+                    }
+                    Ok(WorkerMessage::NewChampion(route, iteration)) => {
+                        progress.set_iterations(worker, iteration);
+                        if progress.offer_champion(route.clone()) {
+                            simulation_event_callback(SimulationEvent::NewChampion(
+                                route,
+                                progress.total_iterations(),
+                            ));
+                        }
+                    }
+                    Ok(WorkerMessage::MeanDistance(mean_distance, iteration)) => {
+                        let iterations = progress.set_iterations(worker, iteration);
+                        mean_distances[worker] = Some(mean_distance);
+                        let known_means: Vec<f64> =
+                            mean_distances.iter().flatten().copied().collect();
+                        let mean_distance =
+                            known_means.iter().sum::<f64>() / known_means.len() as f64;
+                        simulation_event_callback(SimulationEvent::MeanDistance(
+                            mean_distance,
+                            iterations,
+                        ));
+                    }
+                    Ok(WorkerMessage::Emigrants(emigrants)) => {
+                        for to in self.migration_destinations(worker, &done, &mut rng) {
+                            let immigrants = CoordinatorMessage::Immigrants(emigrants.clone());
+                            // a worker that just finished may have closed its connection
+                            if send_message(&mut writers[to], &immigrants).is_ok() {
+                                simulation_event_callback(SimulationEvent::Migration(
+                                    worker,
+                                    to,
+                                    emigrants.len(),
+                                ));
+                            }
+                        }
+                    }
+                    Ok(WorkerMessage::Finished(route)) => {
+                        done[worker] = true;
+                        routes.push(route);
+                    }
+                    Ok(WorkerMessage::Failed(message)) | Err(message) => {
+                        done[worker] = true;
+                        simulation_event_callback(SimulationEvent::WorkerFailed(
+                            worker,
+                            message.clone(),
+                        ));
+                        last_failure = Some(message);
+                    }
+                }
+            }
+
+            if done.iter().all(|done| *done) {
+                break;
+            }
+
+            match stop_deadline {
+                Some(stop_deadline) if Instant::now() >= stop_deadline => break,
+                Some(_) => {}
+                None => {
+                    if control.is_paused() {
+                        broadcast(&mut writers, &done, &CoordinatorMessage::Pause);
+                        if let (Some(paused), Some(stop_condition)) =
+                            (control.wait_while_paused(), &mut stop_condition)
+                        {
+                            stop_condition.exclude(paused);
+                        }
+                        broadcast(&mut writers, &done, &CoordinatorMessage::Resume);
+                    }
+
+                    let met_condition = stop_condition.as_mut().and_then(|stop_condition| {
+                        stop_condition.met_condition(&progress.simulation_progress())
+                    });
+                    if let Some(met_condition) = met_condition {
+                        simulation_event_callback(SimulationEvent::StopConditionMet(
+                            met_condition.clone(),
+                        ));
+                    }
+                    if control.is_stopped() || met_condition.is_some() {
+                        // keep receiving until the workers send their final routes
+                        broadcast(&mut writers, &done, &CoordinatorMessage::Stop);
+                        stop_deadline = Some(Instant::now() + STOP_TIMEOUT);
+                    }
+                }
+            }
+        }
+
+        simulation_event_callback(SimulationEvent::Finished);
+
+        for writer in &writers {
+            let _ = writer.shutdown(Shutdown::Both);
+        }
+
+        progress.best_route(routes).ok_or_else(|| {
+            SimulationError::Network(
+                last_failure.unwrap_or_else(|| "no worker found a route".to_owned()),
+            )
+        })
+    }
+}
+
+impl DistributedSimulation {
+    /// Listens for workers on the address; use port 0 to let the system pick a free port, and
+    /// `local_addr` to find it.
+    pub fn bind<A: ToSocketAddrs>(
+        address: A,
+        locations: Vec<Location>,
+        workers: usize,
+    ) -> Result<DistributedSimulation, SimulationError> {
+        Ok(DistributedSimulation {
+            listener: TcpListener::bind(address).map_err(network_error)?,
+            locations,
+            workers,
+            connect_timeout: Duration::from_secs(60),
+            island_model: None,
+            stop_condition: None,
+            seed: None,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, SimulationError> {
+        self.listener.local_addr().map_err(network_error)
+    }
+
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        if self.workers == 0 {
+            return Err(SimulationError::NoThreads);
+        }
+        if let Some(island_model) = &self.island_model {
+            island_model.validate()?;
+        }
+        if let Some(stop_condition) = &self.stop_condition {
+            stop_condition.validate()?;
+        }
+        Ok(())
+    }
+
+    // Waits until all the workers are connected
+    fn accept_workers(
+        &self,
+        control: &SimulationControl,
+    ) -> Result<Vec<TcpStream>, SimulationError> {
+        self.listener.set_nonblocking(true).map_err(network_error)?;
+        let deadline = Instant::now() + self.connect_timeout;
+        let mut streams = Vec::<TcpStream>::with_capacity(self.workers);
+
+        while streams.len() < self.workers {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false).map_err(network_error)?;
+                    streams.push(stream);
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                    if control.is_stopped() || Instant::now() >= deadline {
+                        return Err(SimulationError::Network(format!(
+                            "only {} of {} workers connected",
+                            streams.len(),
+                            self.workers
+                        )));
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+                Err(error) => return Err(network_error(error)),
+            }
+        }
+        Ok(streams)
+    }
+
+    // Workers still running that receive the emigrants of the worker
+    fn migration_destinations(
+        &self,
+        worker: usize,
+        done: &[bool],
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<usize> {
+        let Some(island_model) = &self.island_model else {
+            return vec![];
+        };
+        let neighbours: Vec<usize> = island_model
+            .topology
+            .neighbours(worker, done.len())
+            .into_iter()
+            .filter(|neighbour| !done[*neighbour])
+            .collect();
+        match island_model.topology {
+            MigrationTopology::Random => neighbours.choose(rng).into_iter().copied().collect(),
+            _ => neighbours,
+        }
+    }
+}
+
+// Exchanges routes with the other workers through the coordinator
+struct RemoteMigration<'a> {
+    job: &'a WorkerJob,
+    writer: &'a Mutex<TcpStream>,
+    inbox: &'a Mutex<Vec<Route>>,
+}
+
+impl Migration for RemoteMigration<'_> {
+    fn interval(&self) -> usize {
+        self.job.migration_interval
+    }
+
+    fn size(&self) -> usize {
+        self.job.migration_size
+    }
+
+    fn migrate(&self, emigrants: &[Route]) -> Vec<Route> {
+        let emigrants = WorkerMessage::Emigrants(emigrants.to_vec());
+        let _ = send_message(&mut self.writer.lock().unwrap(), &emigrants);
+        mem::take(&mut *self.inbox.lock().unwrap())
+    }
+}
+
+/// Connects to a `DistributedSimulation` coordinator and runs the simulation created for the job
+/// it sends, streaming the progress back until the simulation finishes or the coordinator stops it.
+pub fn run_worker<A, S, F>(address: A, factory: F) -> Result<Route, SimulationError>
+where
+    A: ToSocketAddrs,
+    S: Simulation,
+    F: FnOnce(&WorkerJob) -> S,
+{
+    let stream = TcpStream::connect(address).map_err(network_error)?;
+    let writer = Mutex::new(stream.try_clone().map_err(network_error)?);
+    let mut messages = receive_messages::<CoordinatorMessage>(stream);
+    let job = match messages.next() {
+        Some(Ok(CoordinatorMessage::Job(job))) => job,
+        Some(Err(error)) => return Err(network_error(error)),
+        _ => {
+            return Err(SimulationError::Network(
+                "the coordinator did not send a job".to_owned(),
+            ))
+        }
+    };
+    let simulation = factory(&job);
+
+    let control = SimulationControl::new();
+    let inbox = Arc::new(Mutex::new(Vec::<Route>::new()));
+    {
+        let control = control.clone();
+        let inbox = inbox.clone();
+        thread::spawn(move || {
+            for message in messages {
+                match message {
+                    Ok(CoordinatorMessage::Immigrants(routes)) => {
+                        inbox.lock().unwrap().extend(routes)
+                    }
+                    Ok(CoordinatorMessage::Pause) => control.pause(),
+                    Ok(CoordinatorMessage::Resume) => control.resume(),
+                    Ok(CoordinatorMessage::Job(_)) => {}
+                    Ok(CoordinatorMessage::Stop) | Err(_) => break,
+                }
+            }
+            // stopped, or the coordinator is gone
+            control.stop();
+        });
+    }
+
+    // the coordinator may be gone, the simulation then stops on its own
+    let send = |message: WorkerMessage| {
+        let _ = send_message(&mut writer.lock().unwrap(), &message);
+    };
+    // Started and Finished are sent here, so that the coordinator does not depend on every kind
+    // of simulation sending them
+    let simulation_event_callback = |event| match event {
+        SimulationEvent::Iteration(iteration) => send(WorkerMessage::Iteration(iteration)),
+        SimulationEvent::Evaluations(evaluations) => send(WorkerMessage::Evaluations(evaluations)),
+        SimulationEvent::NewChampion(route, iteration) => {
+            send(WorkerMessage::NewChampion(route, iteration))
+        }
+        SimulationEvent::MeanDistance(mean_distance, iteration) => {
+            send(WorkerMessage::MeanDistance(mean_distance, iteration))
+        }
+        _ => {}
+    };
+
+    send(WorkerMessage::Started);
+    let result = if job.migration_interval > 0 {
+        let migration = RemoteMigration {
+            job: &job,
+            writer: &writer,
+            inbox: &inbox,
+        };
+        simulation.run_with_migration(&control, &migration, simulation_event_callback)
+    } else {
+        simulation.run(&control, simulation_event_callback)
+    };
+    match &result {
+        Ok(route) => send(WorkerMessage::Finished(route.clone())),
+        Err(error) => send(WorkerMessage::Failed(error.to_string())),
+    }
+    let _ = writer.lock().unwrap().shutdown(Shutdown::Write);
+
+    result
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tsp_sim_agent::{GeneticSimulation, InstanceGenerator, Shape};
+
+    #[test]
+    fn test_distributed_workers_on_localhost() {
+        let locations: Vec<Location> = InstanceGenerator::new(Shape::Grid, 15, 0)
+            .generate()
+            .unwrap();
+
+        let mut simulation = DistributedSimulation::bind("127.0.0.1:0", locations, 2).unwrap();
+        simulation.island_model = Some(IslandModel {
+            topology: MigrationTopology::Ring,
+            interval: 20,
+            size: 2,
+        });
+        let address = simulation.local_addr().unwrap();
+
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                thread::spawn(move || {
+                    run_worker(address, |job| GeneticSimulation {
+                        max_iterations: Some(300),
+                        assume_convergence: None,
+                        ..GeneticSimulation::new(job.locations.clone())
+                    })
+                })
+            })
+            .collect();
+
+        let migrations = AtomicUsize::new(0);
+        let solution = simulation.run(&SimulationControl::new(), |event| {
+            if let SimulationEvent::Migration(_, _, _) = event {
+                migrations.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        let worker_routes: Vec<Route> = workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap().unwrap())
+            .collect();
+        let best_worker_distance = worker_routes
+            .iter()
+            .map(|route| route.distance)
+            .fold(f64::MAX, f64::min);
+        assert_eq!(
+            solution.map(|route| route.distance),
+            Ok(best_worker_distance)
+        );
+        assert!(migrations.load(Ordering::Relaxed) > 0);
+    }
+}
@@ -0,0 +1,195 @@
+use rand::prelude::{SeedableRng, SliceRandom};
+use rand_chacha::ChaCha8Rng;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Mutex;
+use tsp_sim_agent::{Migration, Route, SimulationError, SimulationEvent};
+
+/// Which islands receive the emigrants of each island.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationTopology {
+    /// Each island sends to the next one.
+    Ring,
+    /// Each island sends to every other island.
+    FullyConnected,
+    /// Each island sends to another island chosen at random on every migration.
+    Random,
+}
+
+/// Periodic exchange of the best routes between the threads of a `ParallelSimulation`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IslandModel {
+    pub topology: MigrationTopology,
+    /// Iterations between migrations.
+    pub interval: usize,
+    /// Number of routes sent on each migration.
+    pub size: usize,
+}
+
+impl Default for IslandModel {
+    fn default() -> Self {
+        Self {
+            topology: MigrationTopology::Ring,
+            interval: 1000,
+            size: 2,
+        }
+    }
+}
+
+impl IslandModel {
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        if self.interval == 0 {
+            return Err(SimulationError::InvalidIslandModel(
+                "migration interval must be at least one iteration".to_owned(),
+            ));
+        }
+        if self.size == 0 {
+            return Err(SimulationError::InvalidIslandModel(
+                "migration size must be at least one route".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Channels connecting the islands according to the topology, one entry per island
+    pub(crate) fn connect(&self, islands: usize) -> Vec<IslandLinks> {
+        let (senders, receivers): (Vec<_>, Vec<_>) =
+            (0..islands).map(|_| mpsc::channel::<Vec<Route>>()).unzip();
+
+        receivers
+            .into_iter()
+            .enumerate()
+            .map(|(index, inbox)| {
+                let outboxes = self
+                    .topology
+                    .neighbours(index, islands)
+                    .into_iter()
+                    .map(|other| (other, senders[other].clone()))
+                    .collect();
+                IslandLinks { inbox, outboxes }
+            })
+            .collect()
+    }
+}
+
+impl MigrationTopology {
+    // Islands that can receive the emigrants of the island; `Random` picks one of them each time
+    pub(crate) fn neighbours(&self, island: usize, islands: usize) -> Vec<usize> {
+        match self {
+            MigrationTopology::Ring if islands > 1 => vec![(island + 1) % islands],
+            MigrationTopology::Ring => vec![],
+            MigrationTopology::FullyConnected | MigrationTopology::Random => {
+                (0..islands).filter(|other| *other != island).collect()
+            }
+        }
+    }
+}
+
+pub(crate) struct IslandLinks {
+    inbox: Receiver<Vec<Route>>,
+    outboxes: Vec<(usize, Sender<Vec<Route>>)>,
+}
+
+pub(crate) struct Island {
+    index: usize,
+    model: IslandModel,
+    links: IslandLinks,
+    event_sender: Sender<(usize, SimulationEvent)>,
+    // picks the destinations of `MigrationTopology::Random`
+    rng: Mutex<ChaCha8Rng>,
+}
+
+impl Island {
+    // Seeded islands pick their destinations from `seed + index`
+    pub(crate) fn new(
+        index: usize,
+        model: IslandModel,
+        links: IslandLinks,
+        event_sender: Sender<(usize, SimulationEvent)>,
+        seed: Option<u64>,
+    ) -> Self {
+        let rng = match seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed.wrapping_add(index as u64)),
+            None => ChaCha8Rng::from_entropy(),
+        };
+        Self {
+            index,
+            model,
+            links,
+            event_sender,
+            rng: Mutex::new(rng),
+        }
+    }
+}
+
+impl Migration for Island {
+    fn interval(&self) -> usize {
+        self.model.interval
+    }
+
+    fn size(&self) -> usize {
+        self.model.size
+    }
+
+    fn migrate(&self, emigrants: &[Route]) -> Vec<Route> {
+        let destinations = match self.model.topology {
+            MigrationTopology::Random => self
+                .links
+                .outboxes
+                .choose(&mut *self.rng.lock().unwrap())
+                .into_iter()
+                .collect(),
+            _ => self.links.outboxes.iter().collect::<Vec<_>>(),
+        };
+
+        for (to, outbox) in destinations {
+            // islands that already finished are no longer receiving
+            if outbox.send(emigrants.to_vec()).is_ok() {
+                let _ = self.event_sender.send((
+                    self.index,
+                    SimulationEvent::Migration(self.index, *to, emigrants.len()),
+                ));
+            }
+        }
+
+        self.links.inbox.try_iter().flatten().collect()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Destinations of ten migrations from the first of four islands
+    fn random_destinations(seed: Option<u64>) -> Vec<usize> {
+        let model = IslandModel {
+            topology: MigrationTopology::Random,
+            ..IslandModel::default()
+        };
+        let (event_sender, event_receiver) = mpsc::channel();
+        // the other islands' inboxes are kept open, so that they receive
+        let mut links = model.connect(4);
+        let island = Island::new(0, model, links.remove(0), event_sender, seed);
+        for _ in 0..10 {
+            island.migrate(&[]);
+        }
+        drop(island);
+        event_receiver
+            .into_iter()
+            .filter_map(|(_, event)| match event {
+                SimulationEvent::Migration(_, to, _) => Some(to),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_seeded_random_topology() {
+        let destinations = random_destinations(Some(7));
+        assert_eq!(destinations.len(), 10);
+        assert!(destinations.iter().all(|to| (1..4).contains(to)));
+        assert_eq!(random_destinations(Some(7)), destinations);
+    }
+}
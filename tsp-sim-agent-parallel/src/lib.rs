@@ -1,17 +1,21 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::Receiver;
-use std::sync::{mpsc, Arc};
-use std::thread;
-use std::thread::JoinHandle;
-use tsp_sim_agent::{GeneticSimulation, Location, Route, Simulation, SimulationEvent};
-
-const NUM_THREADS: usize = 2;
-
-struct ThreadControl {
-    event_receiver: Receiver<SimulationEvent>,
-    stop: Arc<AtomicBool>,
-    join_handle: JoinHandle<Route>,
-}
+mod coordinator;
+mod distributed;
+mod island;
+mod portfolio;
+mod runner;
+
+pub use distributed::{
+    run_worker, CoordinatorMessage, DistributedSimulation, WorkerJob, WorkerMessage,
+};
+pub use island::{IslandModel, MigrationTopology};
+pub use portfolio::{Portfolio, PortfolioConfiguration};
+pub use runner::{ParallelRunner, SimulationFactory};
+
+use std::sync::Arc;
+use tsp_sim_agent::{
+    Checkpoint, GeneticCheckpoint, GeneticSimulation, Location, Route, Simulation,
+    SimulationControl, SimulationError, SimulationEvent, StopCondition,
+};
 
 #[derive(Debug, Clone)]
 pub struct ParallelSimulation {
@@ -19,92 +23,35 @@ pub struct ParallelSimulation {
     pub population_size: usize,
     pub max_iterations: Option<usize>,
     pub assume_convergence: Option<usize>,
+    /// Evaluated on the totals aggregated over all threads.
+    pub stop_condition: Option<StopCondition>,
+    pub threads: usize,
+    /// Pins each thread to a CPU core, round-robin.
+    pub pin_threads: bool,
+    /// Exchanges routes between the threads instead of running them independently.
+    pub island_model: Option<IslandModel>,
+    /// How many times a thread that failed is restarted, from the current champion.
+    pub worker_restarts: usize,
+    /// Seeds the thread with index `i` with `seed + i`.
+    pub seed: Option<u64>,
+    /// Iterations of each thread between `Checkpoint` events, which gather the latest checkpoint
+    /// of every thread.
+    pub checkpoint_interval: Option<usize>,
+    /// Resumes each thread from its checkpoint.
+    pub checkpoint: Option<Checkpoint>,
 }
 
 impl Simulation for ParallelSimulation {
-    fn run<F>(&self, stop: &Arc<AtomicBool>, simulation_event_callback: F) -> Route
+    fn run<F>(
+        &self,
+        control: &SimulationControl,
+        simulation_event_callback: F,
+    ) -> Result<Route, SimulationError>
     where
         F: Fn(SimulationEvent),
     {
-        assert!(NUM_THREADS > 0);
-
-        let controls: Vec<(usize, ThreadControl)> = (0..NUM_THREADS)
-            .map(|index| (index, self.spawn_simulation_agent()))
-            .collect();
-
-        let thread_count = controls.len();
-        let mut started_count: usize = 0;
-        let mut finished_count: usize = 0;
-        let mut iterations: [usize; NUM_THREADS] = [0; NUM_THREADS];
-        let mut champion = Route {
-            locations: vec![],
-            distance: f64::MAX,
-        };
-
-        loop {
-            let simulation_events: Vec<(usize, SimulationEvent)> = controls
-                .iter()
-                .filter_map(|(index, control)| {
-                    control
-                        .event_receiver
-                        .try_recv()
-                        .ok()
-                        .map(|event| (index.clone(), event))
-                })
-                .collect();
-
-            for (index, simulation_event) in simulation_events {
-                match simulation_event {
-                    SimulationEvent::Started => {
-                        started_count += 1;
-                        if started_count >= thread_count {
-                            simulation_event_callback(SimulationEvent::Started);
-                        }
-                    }
-                    SimulationEvent::Finished => {
-                        finished_count += 1;
-                    }
-                    SimulationEvent::Iteration(iteration) => {
-                        iterations[index] = iteration;
-                        let iterations = iterations.iter().sum();
-                        simulation_event_callback(SimulationEvent::Iteration(iterations));
-                    }
-                    SimulationEvent::NewChampion(route, iteration) => {
-                        iterations[index] = iteration;
-                        if route.distance < champion.distance {
-                            let iterations = iterations.iter().sum();
-                            champion = route;
-                            simulation_event_callback(SimulationEvent::NewChampion(
-                                champion.clone(),
-                                iterations,
-                            ));
-                        }
-                    }
-                }
-            }
-
-            if finished_count >= thread_count {
-                break;
-            }
-
-            if stop.load(Ordering::Relaxed) {
-                break;
-            }
-        }
-
-        simulation_event_callback(SimulationEvent::Finished);
-
-        controls
-            .iter()
-            .for_each(|(_, control)| control.stop.store(true, Ordering::Relaxed));
-
-        let mut routes: Vec<Route> = controls
-            .into_iter()
-            .map(|(_, control)| control.join_handle.join().unwrap())
-            .collect();
-
-        routes.sort_by(|r1, r2| r1.distance.total_cmp(&r2.distance));
-        routes[0].clone()
+        self.validate()?;
+        self.runner().run(control, simulation_event_callback)
     }
 }
 
@@ -115,29 +62,127 @@ impl ParallelSimulation {
             population_size: 200,
             max_iterations: Some(100_000),
             assume_convergence: Some(25_000),
+            stop_condition: None,
+            threads: runner::default_threads(),
+            pin_threads: false,
+            island_model: None,
+            worker_restarts: 0,
+            seed: None,
+            checkpoint_interval: None,
+            checkpoint: None,
         }
     }
 
-    fn spawn_simulation_agent(&self) -> ThreadControl {
-        let sim = GeneticSimulation::from(self.clone());
-        let (event_sender, event_receiver) = mpsc::channel::<SimulationEvent>();
-        let stop = Arc::new(AtomicBool::new(false));
-        let stop2 = stop.clone();
+    pub fn builder(locations: Vec<Location>) -> ParallelSimulationBuilder {
+        ParallelSimulationBuilder {
+            simulation: ParallelSimulation::new(locations),
+        }
+    }
 
-        let join_handle = thread::spawn(move || {
-            sim.run(&stop2, |event| {
-                event_sender.send(event).unwrap();
-            })
-        });
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        if self.threads == 0 {
+            return Err(SimulationError::NoThreads);
+        }
+        GeneticSimulation::from(self.clone()).validate()?;
+        if let Some(island_model) = &self.island_model {
+            island_model.validate()?;
+        }
+        if let Some(stop_condition) = &self.stop_condition {
+            stop_condition.validate()?;
+        }
+        Ok(())
+    }
 
-        ThreadControl {
-            event_receiver,
-            stop,
-            join_handle,
+    // Runs a `GeneticSimulation` with this configuration on every thread
+    fn runner(&self) -> ParallelRunner<GeneticSimulation> {
+        let simulation = GeneticSimulation::from(self.clone());
+        let checkpoint = self.checkpoint.clone();
+        ParallelRunner {
+            stop_condition: self.stop_condition.clone(),
+            threads: self.threads,
+            pin_threads: self.pin_threads,
+            island_model: self.island_model.clone(),
+            worker_restarts: self.worker_restarts,
+            seed: self.seed,
+            ..ParallelRunner::new(Arc::new(move |index, initial_routes| GeneticSimulation {
+                locations: simulation.locations.clone(),
+                stop_condition: simulation.stop_condition.clone(),
+                initial_routes,
+                seed: simulation.seed.map(|seed| seed.wrapping_add(index as u64)),
+                checkpoint: genetic_checkpoint(checkpoint.as_ref(), index),
+                ..simulation
+            }))
         }
     }
 }
 
+/// Builds a `ParallelSimulation`, validating its configuration.
+pub struct ParallelSimulationBuilder {
+    simulation: ParallelSimulation,
+}
+
+impl ParallelSimulationBuilder {
+    pub fn population_size(mut self, population_size: usize) -> Self {
+        self.simulation.population_size = population_size;
+        self
+    }
+
+    pub fn max_iterations(mut self, max_iterations: Option<usize>) -> Self {
+        self.simulation.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn assume_convergence(mut self, assume_convergence: Option<usize>) -> Self {
+        self.simulation.assume_convergence = assume_convergence;
+        self
+    }
+
+    pub fn stop_condition(mut self, stop_condition: Option<StopCondition>) -> Self {
+        self.simulation.stop_condition = stop_condition;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.simulation.threads = threads;
+        self
+    }
+
+    pub fn pin_threads(mut self, pin_threads: bool) -> Self {
+        self.simulation.pin_threads = pin_threads;
+        self
+    }
+
+    pub fn island_model(mut self, island_model: Option<IslandModel>) -> Self {
+        self.simulation.island_model = island_model;
+        self
+    }
+
+    pub fn worker_restarts(mut self, worker_restarts: usize) -> Self {
+        self.simulation.worker_restarts = worker_restarts;
+        self
+    }
+
+    pub fn seed(mut self, seed: Option<u64>) -> Self {
+        self.simulation.seed = seed;
+        self
+    }
+
+    pub fn checkpoint_interval(mut self, checkpoint_interval: Option<usize>) -> Self {
+        self.simulation.checkpoint_interval = checkpoint_interval;
+        self
+    }
+
+    pub fn checkpoint(mut self, checkpoint: Option<Checkpoint>) -> Self {
+        self.simulation.checkpoint = checkpoint;
+        self
+    }
+
+    pub fn build(self) -> Result<ParallelSimulation, SimulationError> {
+        self.simulation.validate()?;
+        Ok(self.simulation)
+    }
+}
+
 impl From<ParallelSimulation> for GeneticSimulation {
     fn from(parallel: ParallelSimulation) -> Self {
         Self {
@@ -145,6 +190,119 @@ impl From<ParallelSimulation> for GeneticSimulation {
             population_size: parallel.population_size,
             max_iterations: parallel.max_iterations,
             assume_convergence: parallel.assume_convergence,
+            stop_condition: None,
+            initial_routes: vec![],
+            parallel_evaluation: None,
+            seed: parallel.seed,
+            checkpoint_interval: parallel.checkpoint_interval,
+            checkpoint: genetic_checkpoint(parallel.checkpoint.as_ref(), 0),
         }
     }
 }
+
+// The checkpoint of the genetic simulation run by a thread
+fn genetic_checkpoint(checkpoint: Option<&Checkpoint>, index: usize) -> Option<GeneticCheckpoint> {
+    match checkpoint.and_then(|checkpoint| checkpoint.worker(index)) {
+        Some(Checkpoint::Genetic(checkpoint)) => Some(checkpoint.as_ref().clone()),
+        _ => None,
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use tsp_sim_agent::{InstanceGenerator, Shape};
+
+    #[test]
+    fn test_simulate_2_locations() {
+        let locations = vec![
+            Location {
+                name: "A".to_owned(),
+                x: 0.0,
+                y: 0.0,
+            },
+            Location {
+                name: "B".to_owned(),
+                x: 0.0,
+                y: 10.0,
+            },
+        ];
+
+        let simulation = ParallelSimulation::new(locations);
+        let solution = simulation.run(&SimulationControl::new(), |_| {});
+        assert_eq!(solution.map(|route| route.distance), Ok(10.0));
+    }
+
+    #[test]
+    fn test_island_model_migrates_routes() {
+        let locations = InstanceGenerator::new(Shape::Grid, 20, 0)
+            .generate()
+            .unwrap();
+
+        let simulation = ParallelSimulation::builder(locations)
+            .threads(3)
+            .max_iterations(Some(200))
+            .assume_convergence(None)
+            .island_model(Some(IslandModel {
+                topology: MigrationTopology::Ring,
+                interval: 10,
+                size: 2,
+            }))
+            .build()
+            .unwrap();
+
+        let migrations = AtomicUsize::new(0);
+        let solution = simulation.run(&SimulationControl::new(), |event| {
+            if let SimulationEvent::Migration(from, to, _) = event {
+                assert_eq!(to, (from + 1) % 3);
+                migrations.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        assert!(solution.is_ok());
+        assert!(migrations.load(Ordering::Relaxed) > 0);
+    }
+
+    #[test]
+    fn test_worker_statistics() {
+        let locations = InstanceGenerator::new(Shape::Grid, 20, 0)
+            .generate()
+            .unwrap();
+
+        let simulation = ParallelSimulation::builder(locations)
+            .threads(2)
+            .max_iterations(Some(1_000))
+            .assume_convergence(None)
+            .build()
+            .unwrap();
+
+        let statistics = Mutex::new(vec![]);
+        let solution = simulation
+            .run(&SimulationControl::new(), |event| {
+                if let SimulationEvent::WorkerStatistics(worker_statistics) = event {
+                    *statistics.lock().unwrap() = worker_statistics;
+                }
+            })
+            .unwrap();
+
+        let statistics = statistics.into_inner().unwrap();
+        assert_eq!(statistics.len(), 2);
+        assert!(statistics.iter().all(|worker| !worker.running));
+        assert_eq!(
+            statistics
+                .iter()
+                .map(|worker| worker.iterations)
+                .sum::<usize>(),
+            2_000
+        );
+        let champions: Vec<_> = statistics
+            .iter()
+            .filter(|worker| worker.holds_champion)
+            .collect();
+        assert_eq!(champions.len(), 1);
+        assert_eq!(champions[0].best_distance, Some(solution.distance));
+    }
+}
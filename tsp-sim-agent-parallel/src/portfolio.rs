@@ -0,0 +1,482 @@
+use crate::coordinator::{self, Progress};
+use crate::runner::{self, SimulationFactory, ThreadControl};
+use std::fmt;
+use std::sync::mpsc::Sender;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use tsp_sim_agent::{
+    BoxedSimulation, PortfolioStanding, Route, Simulation, SimulationControl, SimulationError,
+    SimulationEvent, StopCondition, StopConditionTracker,
+};
+
+/// One of the algorithm configurations raced by a `Portfolio`.
+pub struct PortfolioConfiguration<S = BoxedSimulation> {
+    pub name: String,
+    /// Threads running the configuration at the start of the race.
+    pub threads: usize,
+    pub factory: SimulationFactory<S>,
+}
+
+impl<S> PortfolioConfiguration<S> {
+    pub fn new<F>(name: &str, threads: usize, factory: F) -> Self
+    where
+        F: Fn(usize, Vec<Route>) -> S + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_owned(),
+            threads,
+            factory: Arc::new(factory),
+        }
+    }
+}
+
+impl<S> Clone for PortfolioConfiguration<S> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            threads: self.threads,
+            factory: self.factory.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for PortfolioConfiguration<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PortfolioConfiguration")
+            .field("name", &self.name)
+            .field("threads", &self.threads)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Races several algorithm configurations on the same instance. Every `race_interval` the
+/// configurations are ranked by their best route; those clearly behind the leader are stopped and
+/// their threads are given to the configurations still in the race, starting from the leader.
+#[derive(Clone, Debug)]
+pub struct Portfolio<S = BoxedSimulation> {
+    pub configurations: Vec<PortfolioConfiguration<S>>,
+    /// Time between two rankings of the configurations.
+    pub race_interval: Duration,
+    /// How much longer than the leader's best route, relative to it, the best route of a
+    /// configuration can be without being eliminated.
+    pub elimination_margin: f64,
+    /// Number of best configurations that are never eliminated.
+    pub survivors: usize,
+    /// Evaluated on the totals aggregated over all threads.
+    pub stop_condition: Option<StopCondition>,
+}
+
+// A thread running one of the configurations
+struct Worker {
+    configuration: usize,
+    control: ThreadControl,
+    running: bool,
+    iterations: usize,
+}
+
+impl<S> Simulation for Portfolio<S>
+where
+    S: Simulation + Send + 'static,
+{
+    fn run<F>(
+        &self,
+        control: &SimulationControl,
+        simulation_event_callback: F,
+    ) -> Result<Route, SimulationError>
+    where
+        F: Fn(SimulationEvent),
+    {
+        self.validate()?;
+
+        let (event_sender, event_receiver) = mpsc::channel::<(usize, SimulationEvent)>();
+
+        let mut workers: Vec<Worker> = vec![];
+        for (configuration, portfolio_configuration) in self.configurations.iter().enumerate() {
+            for _ in 0..portfolio_configuration.threads {
+                let worker =
+                    self.spawn_worker(control, configuration, workers.len(), vec![], &event_sender);
+                workers.push(worker);
+            }
+        }
+
+        let mut started = false;
+        let mut champions: Vec<Option<Route>> = vec![None; self.configurations.len()];
+        let mut eliminated: Vec<bool> = vec![false; self.configurations.len()];
+        let mut last_race = Instant::now();
+        let mut progress = Progress::new();
+        let mut stop_condition = self.stop_condition.as_ref().map(StopConditionTracker::new);
+
+        while let Some(simulation_events) = coordinator::receive_events(&event_receiver) {
+            for (index, simulation_event) in simulation_events {
+                match simulation_event {
+                    SimulationEvent::Started => {
+                        // workers given to the leaders start later, only report the race start
+                        if !started {
+                            started = true;
+                            simulation_event_callback(SimulationEvent::Started);
+                        }
+                    }
+                    SimulationEvent::Finished => workers[index].running = false,
+                    SimulationEvent::Iteration(iteration) => {
+                        workers[index].iterations = iteration;
+                        let iterations = progress.set_iterations(index, iteration);
+                        simulation_event_callback(SimulationEvent::Iteration(iterations));
+                    }
+                    SimulationEvent::Evaluations(evaluations) => {
+                        let evaluations = progress.set_evaluations(index, evaluations);
+                        simulation_event_callback(SimulationEvent::Evaluations(evaluations));
+                    }
+                    SimulationEvent::NewChampion(route, iteration) => {
+                        workers[index].iterations = iteration;
+                        progress.set_iterations(index, iteration);
+                        let configuration = workers[index].configuration;
+                        let configuration_champion = &mut champions[configuration];
+                        if configuration_champion
+                            .as_ref()
+                            .is_none_or(|champion| route.distance < champion.distance)
+                        {
+                            *configuration_champion = Some(route.clone());
+                        }
+                        if progress.offer_champion(route.clone()) {
+                            simulation_event_callback(SimulationEvent::NewChampion(
+                                route,
+                                progress.total_iterations(),
+                            ));
+                        }
+                    }
+                    SimulationEvent::WorkerFailed(worker, message) => {
+                        workers[index].running = false;
+                        simulation_event_callback(SimulationEvent::WorkerFailed(worker, message));
+                    }
+                    // the mean distances of different algorithms can't be combined, and the
+                    // configurations don't exchange routes
+                    SimulationEvent::MeanDistance(_, _)
+                    | SimulationEvent::Migration(_, _, _)
+                    | SimulationEvent::Leaderboard(_)
+                    | SimulationEvent::WorkerStatistics(_)
+                    | SimulationEvent::Checkpoint(_)
+                    | SimulationEvent::StopConditionMet(_) => {}
+                }
+            }
+
+            if workers.iter().all(|worker| !worker.running) {
+                break;
+            }
+
+            if let Some(paused) = control.wait_while_paused() {
+                if let Some(stop_condition) = &mut stop_condition {
+                    stop_condition.exclude(paused);
+                }
+                last_race += paused;
+            }
+            if control.is_stopped() {
+                break;
+            }
+
+            if let Some(met_condition) = stop_condition.as_mut().and_then(|stop_condition| {
+                stop_condition.met_condition(&progress.simulation_progress())
+            }) {
+                simulation_event_callback(SimulationEvent::StopConditionMet(met_condition.clone()));
+                break;
+            }
+
+            if last_race.elapsed() >= self.race_interval {
+                last_race = Instant::now();
+                self.race(
+                    control,
+                    &champions,
+                    &mut eliminated,
+                    &mut workers,
+                    &event_sender,
+                );
+                simulation_event_callback(SimulationEvent::Leaderboard(self.standings(
+                    &champions,
+                    &eliminated,
+                    &workers,
+                )));
+            }
+        }
+
+        workers.iter().for_each(|worker| worker.control.stop());
+        // the stopped workers are no longer running the configurations
+        workers.iter_mut().for_each(|worker| worker.running = false);
+        simulation_event_callback(SimulationEvent::Leaderboard(self.standings(
+            &champions,
+            &eliminated,
+            &workers,
+        )));
+        simulation_event_callback(SimulationEvent::Finished);
+
+        let results: Vec<Result<Route, SimulationError>> = workers
+            .into_iter()
+            .filter_map(|worker| worker.control.join_handle)
+            .filter_map(|join_handle| join_handle.join().ok())
+            .map(|(result, _)| result)
+            .collect();
+        let mut last_error: Option<SimulationError> = None;
+        let mut routes: Vec<Route> = vec![];
+        for result in results {
+            match result {
+                Ok(route) => routes.push(route),
+                Err(error) => last_error = Some(error),
+            }
+        }
+
+        progress
+            .best_route(routes)
+            .ok_or_else(|| last_error.unwrap_or(SimulationError::NoThreads))
+    }
+}
+
+impl<S> Portfolio<S>
+where
+    S: Simulation + Send + 'static,
+{
+    pub fn new(configurations: Vec<PortfolioConfiguration<S>>) -> Portfolio<S> {
+        Portfolio {
+            configurations,
+            race_interval: Duration::from_secs(5),
+            elimination_margin: 0.05,
+            survivors: 1,
+            stop_condition: None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        if self.configurations.is_empty() {
+            return Err(SimulationError::InvalidPortfolio(
+                "at least one configuration is required".to_owned(),
+            ));
+        }
+        if let Some(configuration) = self.configurations.iter().find(|c| c.threads == 0) {
+            return Err(SimulationError::InvalidPortfolio(format!(
+                "configuration \"{}\" has no threads",
+                configuration.name
+            )));
+        }
+        if self.race_interval.is_zero() {
+            return Err(SimulationError::InvalidPortfolio(
+                "race interval must not be zero".to_owned(),
+            ));
+        }
+        if !self.elimination_margin.is_finite() || self.elimination_margin < 0.0 {
+            return Err(SimulationError::InvalidPortfolio(format!(
+                "elimination margin must be a non-negative number, got {}",
+                self.elimination_margin
+            )));
+        }
+        if self.survivors == 0 {
+            return Err(SimulationError::InvalidPortfolio(
+                "at least one configuration must survive".to_owned(),
+            ));
+        }
+        if let Some(stop_condition) = &self.stop_condition {
+            stop_condition.validate()?;
+        }
+        Ok(())
+    }
+
+    fn spawn_worker(
+        &self,
+        control: &SimulationControl,
+        configuration: usize,
+        index: usize,
+        initial_routes: Vec<Route>,
+        event_sender: &Sender<(usize, SimulationEvent)>,
+    ) -> Worker {
+        let simulation = (self.configurations[configuration].factory)(index, initial_routes);
+        Worker {
+            configuration,
+            control: runner::spawn_worker(
+                simulation,
+                control.child(),
+                index,
+                None,
+                None,
+                event_sender.clone(),
+            ),
+            running: true,
+            iterations: 0,
+        }
+    }
+
+    // Eliminates the configurations too far behind the leader and gives their threads to the
+    // remaining ones, in ranking order, starting from their best route
+    fn race(
+        &self,
+        control: &SimulationControl,
+        champions: &[Option<Route>],
+        eliminated: &mut [bool],
+        workers: &mut Vec<Worker>,
+        event_sender: &Sender<(usize, SimulationEvent)>,
+    ) {
+        // configurations without a route yet can't be compared
+        let mut ranking: Vec<(usize, f64)> = champions
+            .iter()
+            .enumerate()
+            .filter(|(configuration, _)| !eliminated[*configuration])
+            .filter_map(|(configuration, champion)| {
+                champion
+                    .as_ref()
+                    .map(|champion| (configuration, champion.distance))
+            })
+            .collect();
+        ranking.sort_by(|(_, d1), (_, d2)| d1.total_cmp(d2));
+
+        let leader_distance = match ranking.first() {
+            Some((_, distance)) => *distance,
+            None => return,
+        };
+        let limit = leader_distance * (1.0 + self.elimination_margin);
+
+        let mut freed_threads: usize = 0;
+        for (configuration, _) in ranking
+            .iter()
+            .skip(self.survivors)
+            .filter(|(_, distance)| *distance > limit)
+        {
+            eliminated[*configuration] = true;
+            for worker in workers
+                .iter_mut()
+                .filter(|worker| worker.configuration == *configuration && worker.running)
+            {
+                worker.control.stop();
+                worker.running = false;
+                freed_threads += 1;
+            }
+        }
+
+        let leaders: Vec<usize> = ranking
+            .iter()
+            .map(|(configuration, _)| *configuration)
+            .filter(|configuration| !eliminated[*configuration])
+            .collect();
+        for configuration in leaders.iter().cycle().take(freed_threads) {
+            let initial_routes = champions[*configuration].iter().cloned().collect();
+            let worker = self.spawn_worker(
+                control,
+                *configuration,
+                workers.len(),
+                initial_routes,
+                event_sender,
+            );
+            workers.push(worker);
+        }
+    }
+
+    // Standings of the configurations, best first
+    fn standings(
+        &self,
+        champions: &[Option<Route>],
+        eliminated: &[bool],
+        workers: &[Worker],
+    ) -> Vec<PortfolioStanding> {
+        let mut standings: Vec<PortfolioStanding> = self
+            .configurations
+            .iter()
+            .enumerate()
+            .map(|(configuration, portfolio_configuration)| {
+                let configuration_workers = || {
+                    workers
+                        .iter()
+                        .filter(move |worker| worker.configuration == configuration)
+                };
+                PortfolioStanding {
+                    configuration,
+                    name: portfolio_configuration.name.clone(),
+                    champion_distance: champions[configuration]
+                        .as_ref()
+                        .map(|champion| champion.distance),
+                    iterations: configuration_workers()
+                        .map(|worker| worker.iterations)
+                        .sum(),
+                    threads: configuration_workers()
+                        .filter(|worker| worker.running)
+                        .count(),
+                    eliminated: eliminated[configuration],
+                }
+            })
+            .collect();
+        standings.sort_by(|s1, s2| {
+            let distance =
+                |standing: &PortfolioStanding| standing.champion_distance.unwrap_or(f64::INFINITY);
+            distance(s1).total_cmp(&distance(s2))
+        });
+        standings
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tsp_sim_agent::{GeneticSimulation, InstanceGenerator, Location, Shape};
+
+    // A simulation that keeps reporting the same poor route until it is stopped
+    struct StuckSimulation {
+        route: Route,
+    }
+
+    impl Simulation for StuckSimulation {
+        fn run<F>(&self, control: &SimulationControl, callback: F) -> Result<Route, SimulationError>
+        where
+            F: Fn(SimulationEvent),
+        {
+            callback(SimulationEvent::NewChampion(self.route.clone(), 0));
+            while !control.is_stopped() {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            Ok(self.route.clone())
+        }
+    }
+
+    #[test]
+    fn test_portfolio_eliminates_losing_configuration() {
+        let locations: Vec<Location> = InstanceGenerator::new(Shape::Grid, 12, 0)
+            .generate()
+            .unwrap();
+        let stuck_route = Route {
+            locations: locations.clone(),
+            distance: f64::MAX / 2.0,
+        };
+
+        let portfolio = Portfolio {
+            race_interval: Duration::from_millis(100),
+            stop_condition: Some(StopCondition::TimeLimit(Duration::from_millis(500))),
+            ..Portfolio::<BoxedSimulation>::new(vec![
+                PortfolioConfiguration::new("genetic", 1, move |_, initial_routes| {
+                    Box::new(GeneticSimulation {
+                        max_iterations: None,
+                        assume_convergence: None,
+                        initial_routes,
+                        ..GeneticSimulation::new(locations.clone())
+                    }) as BoxedSimulation
+                }),
+                PortfolioConfiguration::new("stuck", 1, move |_, _| {
+                    Box::new(StuckSimulation {
+                        route: stuck_route.clone(),
+                    }) as BoxedSimulation
+                }),
+            ])
+        };
+
+        let leaderboards = Mutex::new(vec![]);
+        let solution = portfolio.run(&SimulationControl::new(), |event| {
+            if let SimulationEvent::Leaderboard(standings) = event {
+                leaderboards.lock().unwrap().push(standings);
+            }
+        });
+        assert!(solution.is_ok());
+
+        // the stuck configuration was eliminated and its thread given to the genetic one
+        let leaderboards = leaderboards.into_inner().unwrap();
+        let first = &leaderboards[0];
+        assert_eq!(first[0].name, "genetic");
+        assert_eq!(first[0].threads, 2);
+        assert_eq!(first[1].name, "stuck");
+        assert!(first[1].eliminated);
+        assert_eq!(first[1].threads, 0);
+    }
+}
@@ -0,0 +1,603 @@
+use crate::coordinator::{self, Progress, POLL_INTERVAL};
+use crate::island::{Island, IslandLinks, IslandModel};
+use core_affinity::CoreId;
+use std::any::Any;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::Sender;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Instant;
+use tsp_sim_agent::{
+    Checkpoint, Route, Simulation, SimulationControl, SimulationError, SimulationEvent,
+    StopCondition, StopConditionTracker, WorkerStatistics,
+};
+
+// The route found by a thread, and the island of a failed thread so it can be reused when the
+// thread is restarted
+pub(crate) type ThreadOutcome = (Result<Route, SimulationError>, Option<Island>);
+
+pub(crate) struct ThreadControl {
+    pub(crate) control: SimulationControl,
+    // taken when a failed thread is joined
+    pub(crate) join_handle: Option<JoinHandle<ThreadOutcome>>,
+}
+
+impl ThreadControl {
+    pub(crate) fn stop(&self) {
+        self.control.stop();
+    }
+}
+
+/// Creates the simulation run by a thread, given the thread index and the routes its simulation
+/// should start from (the current champion, when a failed thread is restarted).
+pub type SimulationFactory<S> = Arc<dyn Fn(usize, Vec<Route>) -> S + Send + Sync>;
+
+/// Runs simulations created by a `SimulationFactory` on several threads, aggregating their events
+/// and returning the best route found by any of them.
+///
+/// Use `BoxedSimulation` as the simulation type to run different kinds of simulations together.
+pub struct ParallelRunner<S> {
+    pub factory: SimulationFactory<S>,
+    /// Evaluated on the totals aggregated over all threads.
+    pub stop_condition: Option<StopCondition>,
+    pub threads: usize,
+    /// Pins each thread to a CPU core, round-robin.
+    pub pin_threads: bool,
+    /// Exchanges routes between the threads instead of running them independently.
+    pub island_model: Option<IslandModel>,
+    /// How many times a thread that failed is restarted, from the current champion.
+    pub worker_restarts: usize,
+    /// Seeds the choices made by the runner itself, like the destinations of
+    /// `MigrationTopology::Random`; the simulations are seeded by the factory.
+    pub seed: Option<u64>,
+}
+
+impl<S> Clone for ParallelRunner<S> {
+    fn clone(&self) -> Self {
+        Self {
+            factory: self.factory.clone(),
+            stop_condition: self.stop_condition.clone(),
+            threads: self.threads,
+            pin_threads: self.pin_threads,
+            island_model: self.island_model.clone(),
+            worker_restarts: self.worker_restarts,
+            seed: self.seed,
+        }
+    }
+}
+
+impl<S> Simulation for ParallelRunner<S>
+where
+    S: Simulation + Send + 'static,
+{
+    fn run<F>(
+        &self,
+        control: &SimulationControl,
+        simulation_event_callback: F,
+    ) -> Result<Route, SimulationError>
+    where
+        F: Fn(SimulationEvent),
+    {
+        self.validate()?;
+
+        let core_ids = if self.pin_threads {
+            core_affinity::get_core_ids().unwrap_or_default()
+        } else {
+            vec![]
+        };
+        let core_id =
+            |index: usize| (!core_ids.is_empty()).then(|| core_ids[index % core_ids.len()]);
+
+        let mut island_links: Vec<Option<IslandLinks>> = match &self.island_model {
+            Some(island_model) => island_model
+                .connect(self.threads)
+                .into_iter()
+                .map(Some)
+                .collect(),
+            None => (0..self.threads).map(|_| None).collect(),
+        };
+
+        // all threads send their events, tagged with the thread index, to the same channel
+        let (event_sender, event_receiver) = mpsc::channel::<(usize, SimulationEvent)>();
+
+        let mut controls: Vec<(usize, ThreadControl)> = (0..self.threads)
+            .map(|index| {
+                let island = self.island(index, island_links[index].take(), &event_sender);
+                let thread_control = self.spawn_simulation_agent(
+                    control,
+                    index,
+                    core_id(index),
+                    island,
+                    vec![],
+                    event_sender.clone(),
+                );
+                (index, thread_control)
+            })
+            .collect();
+        // keep a sender only if it is needed to restart failed threads, so that the channel
+        // disconnects once every thread is done
+        let restart_event_sender = (self.worker_restarts > 0).then_some(event_sender);
+
+        let thread_count = controls.len();
+        let mut started: Vec<bool> = vec![false; thread_count];
+        let mut finished_count: usize = 0;
+        let mut last_error: Option<SimulationError> = None;
+        // iterations and evaluations of the failed runs of each thread
+        let mut base_iterations: Vec<usize> = vec![0; thread_count];
+        let mut base_evaluations: Vec<usize> = vec![0; thread_count];
+        let mut statistics: Vec<WorkerStatistics> = (0..thread_count)
+            .map(|worker| WorkerStatistics {
+                worker,
+                running: true,
+                ..WorkerStatistics::default()
+            })
+            .collect();
+        let mut last_statistics = Instant::now();
+        // latest checkpoint of every thread, and whether it was reported since the last aggregate
+        let mut checkpoints: Vec<Option<Checkpoint>> = vec![None; thread_count];
+        let mut fresh_checkpoints: Vec<bool> = vec![false; thread_count];
+        let mut progress = Progress::new();
+        let mut stop_condition = self.stop_condition.as_ref().map(StopConditionTracker::new);
+
+        // every thread is done once the channel disconnects
+        while let Some(simulation_events) = coordinator::receive_events(&event_receiver) {
+            for (index, simulation_event) in simulation_events {
+                match simulation_event {
+                    SimulationEvent::Started => {
+                        if !started[index] {
+                            started[index] = true;
+                            if started.iter().all(|started| *started) {
+                                simulation_event_callback(SimulationEvent::Started);
+                            }
+                        }
+                    }
+                    SimulationEvent::Finished => {
+                        finished_count += 1;
+                        statistics[index].running = false;
+                    }
+                    SimulationEvent::Iteration(iteration) => {
+                        statistics[index].iterations = base_iterations[index] + iteration;
+                        let iterations =
+                            progress.set_iterations(index, statistics[index].iterations);
+                        simulation_event_callback(SimulationEvent::Iteration(iterations));
+                    }
+                    SimulationEvent::Evaluations(evaluations) => {
+                        statistics[index].evaluations = base_evaluations[index] + evaluations;
+                        let evaluations =
+                            progress.set_evaluations(index, statistics[index].evaluations);
+                        simulation_event_callback(SimulationEvent::Evaluations(evaluations));
+                    }
+                    SimulationEvent::NewChampion(route, iteration) => {
+                        let worker_statistics = &mut statistics[index];
+                        worker_statistics.iterations = base_iterations[index] + iteration;
+                        progress.set_iterations(index, worker_statistics.iterations);
+                        if worker_statistics
+                            .best_distance
+                            .is_none_or(|distance| route.distance < distance)
+                        {
+                            worker_statistics.best_distance = Some(route.distance);
+                        }
+                        if progress.offer_champion(route.clone()) {
+                            statistics.iter_mut().for_each(|worker_statistics| {
+                                worker_statistics.holds_champion = false
+                            });
+                            statistics[index].holds_champion = true;
+                            statistics[index].champions += 1;
+                            simulation_event_callback(SimulationEvent::NewChampion(
+                                route,
+                                progress.total_iterations(),
+                            ));
+                        }
+                    }
+                    SimulationEvent::MeanDistance(mean_distance, iteration) => {
+                        statistics[index].iterations = base_iterations[index] + iteration;
+                        let iterations =
+                            progress.set_iterations(index, statistics[index].iterations);
+                        statistics[index].mean_distance = Some(mean_distance);
+                        let known_means: Vec<f64> = statistics
+                            .iter()
+                            .filter_map(|worker_statistics| worker_statistics.mean_distance)
+                            .collect();
+                        let mean_distance =
+                            known_means.iter().sum::<f64>() / known_means.len() as f64;
+                        simulation_event_callback(SimulationEvent::MeanDistance(
+                            mean_distance,
+                            iterations,
+                        ));
+                    }
+                    SimulationEvent::Migration(from, to, routes) => {
+                        simulation_event_callback(SimulationEvent::Migration(from, to, routes));
+                    }
+                    SimulationEvent::Leaderboard(standings) => {
+                        simulation_event_callback(SimulationEvent::Leaderboard(standings));
+                    }
+                    // a nested parallel simulation, its threads are reported as a single one
+                    // only the stop condition of the whole simulation is reported
+                    SimulationEvent::WorkerStatistics(_) | SimulationEvent::StopConditionMet(_) => {
+                    }
+                    SimulationEvent::Checkpoint(checkpoint) => {
+                        checkpoints[index] = Some(checkpoint);
+                        fresh_checkpoints[index] = true;
+                        // wait until every running thread has reported its checkpoint
+                        if (0..thread_count)
+                            .all(|index| fresh_checkpoints[index] || !statistics[index].running)
+                        {
+                            fresh_checkpoints
+                                .iter_mut()
+                                .for_each(|fresh| *fresh = false);
+                            simulation_event_callback(SimulationEvent::Checkpoint(
+                                Checkpoint::Parallel(checkpoints.clone()),
+                            ));
+                        }
+                    }
+                    SimulationEvent::WorkerFailed(worker, message) => {
+                        simulation_event_callback(SimulationEvent::WorkerFailed(
+                            worker,
+                            message.clone(),
+                        ));
+
+                        // the failed thread is about to end, join it to recover its error and
+                        // island
+                        let thread_control = &mut controls[index].1;
+                        let (result, island) = thread_control
+                            .join_handle
+                            .take()
+                            .and_then(|join_handle| join_handle.join().ok())
+                            .unwrap_or((Err(SimulationError::WorkerPanicked(message)), None));
+                        last_error = result.err();
+
+                        match &restart_event_sender {
+                            Some(event_sender)
+                                if statistics[index].restarts < self.worker_restarts =>
+                            {
+                                statistics[index].restarts += 1;
+                                base_iterations[index] = statistics[index].iterations;
+                                base_evaluations[index] = statistics[index].evaluations;
+                                let initial_routes =
+                                    progress.champion().cloned().into_iter().collect();
+                                *thread_control = self.spawn_simulation_agent(
+                                    control,
+                                    index,
+                                    core_id(index),
+                                    island,
+                                    initial_routes,
+                                    event_sender.clone(),
+                                );
+                            }
+                            _ => {
+                                finished_count += 1;
+                                statistics[index].running = false;
+                            }
+                        }
+                    }
+                }
+            }
+
+            if last_statistics.elapsed() >= POLL_INTERVAL {
+                last_statistics = Instant::now();
+                simulation_event_callback(SimulationEvent::WorkerStatistics(statistics.clone()));
+            }
+
+            if finished_count >= thread_count {
+                break;
+            }
+
+            if let (Some(paused), Some(stop_condition)) =
+                (control.wait_while_paused(), &mut stop_condition)
+            {
+                stop_condition.exclude(paused);
+            }
+            if control.is_stopped() {
+                break;
+            }
+
+            if let Some(met_condition) = stop_condition.as_mut().and_then(|stop_condition| {
+                stop_condition.met_condition(&progress.simulation_progress())
+            }) {
+                simulation_event_callback(SimulationEvent::StopConditionMet(met_condition.clone()));
+                break;
+            }
+        }
+
+        statistics
+            .iter_mut()
+            .for_each(|worker_statistics| worker_statistics.running = false);
+        simulation_event_callback(SimulationEvent::WorkerStatistics(statistics));
+
+        controls
+            .iter()
+            .for_each(|(_, thread_control)| thread_control.stop());
+
+        let routes: Vec<Route> = controls
+            .into_iter()
+            .filter_map(|(_, thread_control)| thread_control.join_handle)
+            .filter_map(|join_handle| join_handle.join().ok())
+            .filter_map(|(result, _)| result.ok())
+            .collect();
+
+        // the threads send a last checkpoint when they stop
+        for (index, simulation_event) in event_receiver.try_iter() {
+            if let SimulationEvent::Checkpoint(checkpoint) = simulation_event {
+                checkpoints[index] = Some(checkpoint);
+            }
+        }
+        if checkpoints.iter().any(Option::is_some) {
+            simulation_event_callback(SimulationEvent::Checkpoint(Checkpoint::Parallel(
+                checkpoints,
+            )));
+        }
+        simulation_event_callback(SimulationEvent::Finished);
+
+        progress
+            .best_route(routes)
+            .ok_or_else(|| last_error.unwrap_or(SimulationError::NoThreads))
+    }
+}
+
+impl<S> ParallelRunner<S>
+where
+    S: Simulation + Send + 'static,
+{
+    pub fn new(factory: SimulationFactory<S>) -> ParallelRunner<S> {
+        ParallelRunner {
+            factory,
+            stop_condition: None,
+            threads: default_threads(),
+            pin_threads: false,
+            island_model: None,
+            worker_restarts: 0,
+            seed: None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        if self.threads == 0 {
+            return Err(SimulationError::NoThreads);
+        }
+        if let Some(island_model) = &self.island_model {
+            island_model.validate()?;
+        }
+        if let Some(stop_condition) = &self.stop_condition {
+            stop_condition.validate()?;
+        }
+        Ok(())
+    }
+
+    fn island(
+        &self,
+        index: usize,
+        island_links: Option<IslandLinks>,
+        event_sender: &Sender<(usize, SimulationEvent)>,
+    ) -> Option<Island> {
+        self.island_model
+            .clone()
+            .zip(island_links)
+            .map(|(model, links)| Island::new(index, model, links, event_sender.clone(), self.seed))
+    }
+
+    fn spawn_simulation_agent(
+        &self,
+        control: &SimulationControl,
+        index: usize,
+        core_id: Option<CoreId>,
+        island: Option<Island>,
+        initial_routes: Vec<Route>,
+        event_sender: Sender<(usize, SimulationEvent)>,
+    ) -> ThreadControl {
+        let simulation = (self.factory)(index, initial_routes);
+        spawn_worker(
+            simulation,
+            control.child(),
+            index,
+            core_id,
+            island,
+            event_sender,
+        )
+    }
+}
+
+// Runs the simulation on a new thread, controlled by the given control, sending its events tagged
+// with the worker index
+pub(crate) fn spawn_worker<S>(
+    sim: S,
+    control: SimulationControl,
+    index: usize,
+    core_id: Option<CoreId>,
+    island: Option<Island>,
+    event_sender: Sender<(usize, SimulationEvent)>,
+) -> ThreadControl
+where
+    S: Simulation + Send + 'static,
+{
+    let control2 = control.clone();
+
+    let join_handle = thread::spawn(move || {
+        if let Some(core_id) = core_id {
+            core_affinity::set_for_current(core_id);
+        }
+        // the coordinator may have already returned
+        let send = |event| {
+            let _ = event_sender.send((index, event));
+        };
+        // Started and Finished are sent here, so that the coordinator does not depend on
+        // every kind of simulation sending them
+        let simulation_event_callback = |event| match event {
+            SimulationEvent::Started | SimulationEvent::Finished => {}
+            event => send(event),
+        };
+
+        send(SimulationEvent::Started);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| match &island {
+            Some(island) => sim.run_with_migration(&control2, island, simulation_event_callback),
+            None => sim.run(&control2, simulation_event_callback),
+        }));
+        match result {
+            Ok(Ok(route)) => {
+                send(SimulationEvent::Finished);
+                // the island holds an event sender, it is only kept to restart a failed thread
+                // so that the channel disconnects once every thread is done
+                (Ok(route), None)
+            }
+            Ok(Err(error)) => {
+                send(SimulationEvent::WorkerFailed(index, error.to_string()));
+                (Err(error), island)
+            }
+            Err(payload) => {
+                let message = panic_message(payload.as_ref());
+                send(SimulationEvent::WorkerFailed(index, message.clone()));
+                (Err(SimulationError::WorkerPanicked(message)), island)
+            }
+        }
+    });
+
+    ThreadControl {
+        control,
+        join_handle: Some(join_handle),
+    }
+}
+
+pub(crate) fn default_threads() -> usize {
+    thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1)
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use tsp_sim_agent::{BoxedSimulation, GeneticSimulation, InstanceGenerator, Location, Shape};
+
+    // A simulation that fails before finding any route
+    struct PanickingSimulation;
+
+    impl Simulation for PanickingSimulation {
+        fn run<F>(&self, _: &SimulationControl, _: F) -> Result<Route, SimulationError>
+        where
+            F: Fn(SimulationEvent),
+        {
+            panic!("no route");
+        }
+    }
+
+    // A simulation that fails after its first run, once it has reported its iterations
+    struct PanickingOnceSimulation {
+        simulation: GeneticSimulation,
+        panicked: Arc<AtomicBool>,
+    }
+
+    impl Simulation for PanickingOnceSimulation {
+        fn run<F>(
+            &self,
+            control: &SimulationControl,
+            simulation_event_callback: F,
+        ) -> Result<Route, SimulationError>
+        where
+            F: Fn(SimulationEvent),
+        {
+            let route = self.simulation.run(control, |event| {
+                // the thread is not finished when it is about to fail
+                if !matches!(event, SimulationEvent::Finished)
+                    || self.panicked.load(Ordering::SeqCst)
+                {
+                    simulation_event_callback(event);
+                }
+            });
+            if !self.panicked.swap(true, Ordering::SeqCst) {
+                panic!("lost the route");
+            }
+            route
+        }
+    }
+
+    #[test]
+    fn test_run_mixed_simulations() {
+        let locations: Vec<Location> = InstanceGenerator::new(Shape::Grid, 10, 0)
+            .generate()
+            .unwrap();
+
+        let runner = ParallelRunner {
+            threads: 2,
+            ..ParallelRunner::<BoxedSimulation>::new(Arc::new(move |index, initial_routes| {
+                if index == 0 {
+                    Box::new(PanickingSimulation)
+                } else {
+                    Box::new(GeneticSimulation {
+                        max_iterations: Some(100),
+                        assume_convergence: None,
+                        initial_routes,
+                        ..GeneticSimulation::new(locations.clone())
+                    })
+                }
+            }))
+        };
+
+        let failures = Mutex::new(vec![]);
+        let solution = runner.run(&SimulationControl::new(), |event| {
+            if let SimulationEvent::WorkerFailed(worker, message) = event {
+                failures.lock().unwrap().push((worker, message));
+            }
+        });
+        assert_eq!(solution.map(|route| route.locations.len()), Ok(10));
+        assert_eq!(
+            failures.into_inner().unwrap(),
+            vec![(0, "no route".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_restart_failed_worker() {
+        let locations: Vec<Location> = InstanceGenerator::new(Shape::Grid, 10, 0)
+            .generate()
+            .unwrap();
+        let panicked = Arc::new(AtomicBool::new(false));
+
+        let runner = ParallelRunner {
+            threads: 1,
+            worker_restarts: 1,
+            ..ParallelRunner::new(Arc::new(move |_, initial_routes| PanickingOnceSimulation {
+                simulation: GeneticSimulation {
+                    max_iterations: Some(2000),
+                    assume_convergence: None,
+                    initial_routes,
+                    ..GeneticSimulation::new(locations.clone())
+                },
+                panicked: panicked.clone(),
+            }))
+        };
+
+        let iterations = Mutex::new(vec![]);
+        let statistics = Mutex::new(vec![]);
+        let solution = runner.run(&SimulationControl::new(), |event| match event {
+            SimulationEvent::Iteration(iteration) => iterations.lock().unwrap().push(iteration),
+            SimulationEvent::WorkerStatistics(worker_statistics) => {
+                *statistics.lock().unwrap() = worker_statistics
+            }
+            _ => {}
+        });
+        assert_eq!(solution.map(|route| route.locations.len()), Ok(10));
+
+        let statistics = statistics.into_inner().unwrap();
+        assert_eq!(statistics[0].restarts, 1);
+        let iterations = iterations.into_inner().unwrap();
+        assert!(iterations.windows(2).all(|pair| pair[0] <= pair[1]));
+        // the iterations of the failed run are kept
+        assert_eq!(iterations.last(), Some(&4000));
+        assert_eq!(statistics[0].iterations, *iterations.last().unwrap());
+    }
+}
@@ -0,0 +1,52 @@
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use tsp_sim_agent::{
+    InstanceGenerator, Location, Shape, Simulation, SimulationControl, StopCondition,
+};
+use tsp_sim_agent_parallel::{DistributedSimulation, IslandModel, MigrationTopology};
+
+#[test]
+fn test_coordinate_worker_processes() {
+    let locations: Vec<Location> = InstanceGenerator::new(Shape::Grid, 15, 0)
+        .generate()
+        .unwrap();
+
+    let mut simulation = DistributedSimulation::bind("127.0.0.1:0", locations, 2).unwrap();
+    simulation.connect_timeout = Duration::from_secs(30);
+    simulation.island_model = Some(IslandModel {
+        topology: MigrationTopology::Ring,
+        interval: 20,
+        size: 2,
+    });
+    simulation.stop_condition = Some(StopCondition::TimeLimit(Duration::from_secs(2)));
+    let address = simulation.local_addr().unwrap();
+
+    let workers: Vec<_> = (0..2)
+        .map(|_| {
+            Command::new(env!("CARGO_BIN_EXE_tsp-sim-worker"))
+                .arg(address.to_string())
+                .arg("50")
+                .stderr(Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+
+    let solution = simulation.run(&SimulationControl::new(), |_| {});
+
+    for mut worker in workers {
+        assert!(worker.wait().unwrap().success());
+    }
+    let route = solution.unwrap();
+    assert_eq!(route.locations.len(), 15);
+    assert!(route.distance.is_finite());
+}
+
+#[test]
+fn test_worker_without_coordinator() {
+    let output = Command::new(env!("CARGO_BIN_EXE_tsp-sim-worker"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("usage:"));
+}
@@ -0,0 +1,52 @@
+use crate::{Route, SimulationError};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Full state of a `GeneticSimulation` after an iteration, from which it can be resumed.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct GeneticCheckpoint {
+    pub population: Vec<Route>,
+    pub mating_pool: Vec<Route>,
+    pub champion: Route,
+    pub iteration: usize,
+    /// Iterations since the champion was found.
+    pub champion_iterations: usize,
+    pub evaluations: usize,
+    pub rng: ChaCha8Rng,
+}
+
+/// State of a running simulation, saved periodically or when the simulation ends.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Checkpoint {
+    Genetic(Box<GeneticCheckpoint>),
+    /// Checkpoints of the threads of a parallel simulation, by thread index; threads that never
+    /// reported one start from scratch when resumed.
+    Parallel(Vec<Option<Checkpoint>>),
+}
+
+impl Checkpoint {
+    /// The checkpoint of a thread of a parallel simulation; a single `Genetic` checkpoint is used
+    /// by the first thread.
+    pub fn worker(&self, index: usize) -> Option<&Checkpoint> {
+        match self {
+            Checkpoint::Genetic(_) if index == 0 => Some(self),
+            Checkpoint::Genetic(_) => None,
+            Checkpoint::Parallel(workers) => workers.get(index).and_then(Option::as_ref),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SimulationError> {
+        let ron = ron::to_string(self).map_err(|error| {
+            SimulationError::Checkpoint(format!("could not serialize: {}", error))
+        })?;
+        fs::write(path, ron).map_err(|error| SimulationError::Checkpoint(error.to_string()))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Checkpoint, SimulationError> {
+        let ron = fs::read_to_string(path)
+            .map_err(|error| SimulationError::Checkpoint(error.to_string()))?;
+        ron::from_str(&ron).map_err(|error| SimulationError::Checkpoint(error.to_string()))
+    }
+}
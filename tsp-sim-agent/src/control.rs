@@ -0,0 +1,120 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often a paused simulation checks whether it was resumed or stopped
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Default, Debug)]
+struct ControlState {
+    stopped: AtomicBool,
+    paused: AtomicBool,
+}
+
+/// Handle to stop, pause and resume a running `Simulation`; clones control the same simulation.
+///
+/// A paused simulation keeps its population and continues where it was when resumed.
+#[derive(Clone, Default, Debug)]
+pub struct SimulationControl {
+    state: Arc<ControlState>,
+    parent: Option<Box<SimulationControl>>,
+}
+
+impl SimulationControl {
+    pub fn new() -> SimulationControl {
+        SimulationControl::default()
+    }
+
+    /// A control that is stopped and paused along with this one, and can also be stopped on its
+    /// own; used to run the parts of a simulation, like the threads of a parallel simulation.
+    pub fn child(&self) -> SimulationControl {
+        SimulationControl {
+            state: Arc::default(),
+            parent: Some(Box::new(self.clone())),
+        }
+    }
+
+    pub fn stop(&self) {
+        self.state.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn pause(&self) {
+        self.state.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.state.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// Clears the stop and pause requests, so that the control can be reused for another run.
+    pub fn reset(&self) {
+        self.state.stopped.store(false, Ordering::Relaxed);
+        self.state.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.state.stopped.load(Ordering::Relaxed)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.is_stopped())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.paused.load(Ordering::Relaxed)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.is_paused())
+    }
+
+    /// Blocks while paused, until resumed or stopped, and returns how long it was paused, or
+    /// `None` if it wasn't.
+    pub fn wait_while_paused(&self) -> Option<Duration> {
+        if !self.is_paused() {
+            return None;
+        }
+        let start = Instant::now();
+        while self.is_paused() && !self.is_stopped() {
+            thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+        Some(start.elapsed())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_child_follows_parent() {
+        let parent = SimulationControl::new();
+        let child = parent.child();
+
+        parent.pause();
+        assert!(child.is_paused());
+        let resume = {
+            let parent = parent.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                parent.resume();
+            })
+        };
+        assert!(child
+            .wait_while_paused()
+            .is_some_and(|paused| paused >= Duration::from_millis(50)));
+        assert!(!child.is_paused());
+        assert_eq!(child.wait_while_paused(), None);
+        resume.join().unwrap();
+
+        child.stop();
+        assert!(!parent.is_stopped());
+        parent.stop();
+        parent.reset();
+        assert!(!parent.is_stopped());
+        assert!(child.is_stopped());
+    }
+}
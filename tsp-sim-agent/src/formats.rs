@@ -0,0 +1,515 @@
+use crate::{Location, Route, SimulationError};
+use serde_json::{json, Value};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// File formats of simulation locations and routes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Ron,
+    Json,
+    /// One `name,x,y` row per location; the header is optional and its columns can be in any
+    /// order, and rows of only `x,y` are named by their number.
+    Csv,
+    /// Points of a FeatureCollection, named by their `name` property; routes are written as a
+    /// LineString.
+    GeoJson,
+    /// TSPLIB instances with `EUC_2D` coordinates, and tours for routes.
+    Tsplib,
+}
+
+impl Format {
+    pub const ALL: [Format; 5] = [
+        Format::Ron,
+        Format::Json,
+        Format::Csv,
+        Format::GeoJson,
+        Format::Tsplib,
+    ];
+
+    /// The format of a file, from its extension; `.tsp` and `.tour` files are TSPLIB.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Format> {
+        let extension = path.as_ref().extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "ron" => Some(Format::Ron),
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            "geojson" => Some(Format::GeoJson),
+            "tsp" | "tour" => Some(Format::Tsplib),
+            _ => None,
+        }
+    }
+
+    /// Extension of locations files; see `route_extension` for routes.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Ron => "ron",
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::GeoJson => "geojson",
+            Format::Tsplib => "tsp",
+        }
+    }
+
+    /// Extension of route files, which differs only for TSPLIB tours.
+    pub fn route_extension(&self) -> &'static str {
+        match self {
+            Format::Tsplib => "tour",
+            format => format.extension(),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Ron => write!(f, "RON"),
+            Format::Json => write!(f, "JSON"),
+            Format::Csv => write!(f, "CSV"),
+            Format::GeoJson => write!(f, "GeoJSON"),
+            Format::Tsplib => write!(f, "TSPLIB"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = SimulationError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "ron" => Ok(Format::Ron),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "geojson" => Ok(Format::GeoJson),
+            "tsp" | "tsplib" => Ok(Format::Tsplib),
+            _ => Err(SimulationError::InvalidInput(format!(
+                "unknown format {}",
+                name
+            ))),
+        }
+    }
+}
+
+fn invalid_input(format: Format, reason: impl fmt::Display) -> SimulationError {
+    SimulationError::InvalidInput(format!("{}: {}", format, reason))
+}
+
+/// Parses the locations of a simulation.
+pub fn read_locations(text: &str, format: Format) -> Result<Vec<Location>, SimulationError> {
+    match format {
+        Format::Ron => ron::from_str(text).map_err(|error| invalid_input(format, error)),
+        Format::Json => serde_json::from_str(text).map_err(|error| invalid_input(format, error)),
+        Format::Csv => read_csv(text),
+        Format::GeoJson => read_geojson(text),
+        Format::Tsplib => read_tsplib(text),
+    }
+}
+
+/// Writes the locations of a simulation; in TSPLIB, their names must be node numbers.
+pub fn write_locations(locations: &[Location], format: Format) -> Result<String, SimulationError> {
+    match format {
+        Format::Ron => ron::ser::to_string_pretty(locations, ron::ser::PrettyConfig::default())
+            .map_err(|error| invalid_input(format, error)),
+        Format::Json => {
+            serde_json::to_string_pretty(locations).map_err(|error| invalid_input(format, error))
+        }
+        Format::Csv => write_csv(locations),
+        Format::GeoJson => {
+            let collection = json!({
+                "type": "FeatureCollection",
+                "features": locations.iter().map(point_feature).collect::<Vec<Value>>(),
+            });
+            serde_json::to_string_pretty(&collection).map_err(|error| invalid_input(format, error))
+        }
+        Format::Tsplib => write_tsplib(locations),
+    }
+}
+
+/// Writes a route; in TSPLIB, as a tour of the location names, which must be node numbers.
+pub fn write_route(route: &Route, format: Format) -> Result<String, SimulationError> {
+    match format {
+        Format::Ron => ron::ser::to_string_pretty(route, ron::ser::PrettyConfig::default())
+            .map_err(|error| invalid_input(format, error)),
+        Format::Json => {
+            serde_json::to_string_pretty(route).map_err(|error| invalid_input(format, error))
+        }
+        Format::Csv => write_csv(&route.locations),
+        Format::GeoJson => {
+            let coordinates: Vec<[f64; 2]> = route
+                .locations
+                .iter()
+                .map(|location| [location.x, location.y])
+                .collect();
+            let names: Vec<&str> = route
+                .locations
+                .iter()
+                .map(|location| location.name.as_str())
+                .collect();
+            let line = json!({
+                "type": "Feature",
+                "geometry": {"type": "LineString", "coordinates": coordinates},
+                "properties": {"distance": route.distance, "names": names},
+            });
+            let features: Vec<Value> = route
+                .locations
+                .iter()
+                .map(point_feature)
+                .chain([line])
+                .collect();
+            let collection = json!({"type": "FeatureCollection", "features": features});
+            serde_json::to_string_pretty(&collection).map_err(|error| invalid_input(format, error))
+        }
+        Format::Tsplib => write_tsplib_tour(route),
+    }
+}
+
+// Rows of name, x and y, with or without a header naming the columns; rows without a name are
+// named by their number
+fn read_csv(text: &str) -> Result<Vec<Location>, SimulationError> {
+    let invalid = |row: usize, reason: &str| {
+        invalid_input(Format::Csv, format!("row {}: {}", row + 1, reason))
+    };
+
+    let records = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .into_records()
+        .collect::<Result<Vec<csv::StringRecord>, _>>()
+        .map_err(|error| invalid_input(Format::Csv, error))?;
+    let Some(first) = records.first() else {
+        return Ok(vec![]);
+    };
+
+    // the first row is a header unless its coordinates are numbers
+    let column = |record: &csv::StringRecord, name: &str| {
+        record
+            .iter()
+            .position(|field| field.eq_ignore_ascii_case(name))
+    };
+    let unnamed = first.len() == 2;
+    let header = first
+        .iter()
+        .skip(if unnamed { 0 } else { 1 })
+        .any(|field| field.parse::<f64>().is_err());
+    let (name_column, x_column, y_column) = if header {
+        match (column(first, "x"), column(first, "y")) {
+            (Some(x), Some(y)) => (column(first, "name"), x, y),
+            _ => return Err(invalid(0, "the header has no x and y columns")),
+        }
+    } else if unnamed {
+        (None, 0, 1)
+    } else {
+        (Some(0), 1, 2)
+    };
+
+    let skipped = usize::from(header);
+    records
+        .iter()
+        .enumerate()
+        .skip(skipped)
+        .map(|(row, record)| {
+            let coordinate = |column: usize| {
+                record
+                    .get(column)
+                    .and_then(|field| field.parse::<f64>().ok())
+                    .ok_or_else(|| invalid(row, "expected numeric x and y"))
+            };
+            let name = match name_column.and_then(|column| record.get(column)) {
+                Some(name) => name.to_owned(),
+                None => (row + 1 - skipped).to_string(),
+            };
+            Ok(Location {
+                name,
+                x: coordinate(x_column)?,
+                y: coordinate(y_column)?,
+            })
+        })
+        .collect()
+}
+
+fn write_csv(locations: &[Location]) -> Result<String, SimulationError> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    locations
+        .iter()
+        .try_for_each(|location| writer.serialize(location))
+        .map_err(|error| invalid_input(Format::Csv, error))?;
+    let bytes = writer
+        .into_inner()
+        .map_err(|error| invalid_input(Format::Csv, error))?;
+    String::from_utf8(bytes).map_err(|error| invalid_input(Format::Csv, error))
+}
+
+// Points of a FeatureCollection, a single Feature or a bare geometry, in order; features without
+// a name are named by their number
+fn read_geojson(text: &str) -> Result<Vec<Location>, SimulationError> {
+    let geojson: Value =
+        serde_json::from_str(text).map_err(|error| invalid_input(Format::GeoJson, error))?;
+    let features: Vec<&Value> = match geojson["type"].as_str() {
+        Some("FeatureCollection") => geojson["features"]
+            .as_array()
+            .map(|features| features.iter().collect())
+            .ok_or_else(|| invalid_input(Format::GeoJson, "features must be an array"))?,
+        Some(_) => vec![&geojson],
+        None => return Err(invalid_input(Format::GeoJson, "missing type")),
+    };
+
+    let mut locations: Vec<Location> = vec![];
+    for feature in features {
+        let (geometry, properties) = match feature["type"].as_str() {
+            Some("Feature") => (&feature["geometry"], &feature["properties"]),
+            _ => (feature, &Value::Null),
+        };
+        if geometry["type"].as_str() != Some("Point") {
+            continue;
+        }
+        let point = geometry["coordinates"].as_array().and_then(|coordinates| {
+            Some((
+                coordinates.first()?.as_f64()?,
+                coordinates.get(1)?.as_f64()?,
+            ))
+        });
+        let Some((x, y)) = point else {
+            return Err(invalid_input(
+                Format::GeoJson,
+                format!("point {} has no coordinates", locations.len() + 1),
+            ));
+        };
+        let name = match &properties["name"] {
+            Value::String(name) => name.clone(),
+            Value::Null => (locations.len() + 1).to_string(),
+            name => name.to_string(),
+        };
+        locations.push(Location { name, x, y });
+    }
+    Ok(locations)
+}
+
+fn point_feature(location: &Location) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": {"type": "Point", "coordinates": [location.x, location.y]},
+        "properties": {"name": location.name},
+    })
+}
+
+// Instance of the TSPLIB library, only with 2D euclidean coordinates, named by node number
+fn read_tsplib(text: &str) -> Result<Vec<Location>, SimulationError> {
+    let invalid = |line: usize, reason: &str| {
+        invalid_input(Format::Tsplib, format!("line {}: {}", line + 1, reason))
+    };
+
+    let mut lines = text.lines().enumerate();
+    let mut dimension: Option<usize> = None;
+    for (line_index, line) in lines.by_ref() {
+        let line = line.trim();
+        if line.starts_with("NODE_COORD_SECTION") {
+            break;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            if line.is_empty() {
+                continue;
+            }
+            return Err(invalid(line_index, "expected a KEY : VALUE specification"));
+        };
+        match (key.trim(), value.trim()) {
+            ("TYPE", "TSP") | ("EDGE_WEIGHT_TYPE", "EUC_2D") => {}
+            ("TYPE", _) => return Err(invalid(line_index, "only TSP instances are supported")),
+            ("EDGE_WEIGHT_TYPE", _) => {
+                return Err(invalid(
+                    line_index,
+                    "only EUC_2D edge weights are supported",
+                ))
+            }
+            ("DIMENSION", value) => {
+                dimension = Some(
+                    value
+                        .parse()
+                        .map_err(|_| invalid(line_index, "invalid dimension"))?,
+                )
+            }
+            _ => {}
+        }
+    }
+
+    let mut locations: Vec<Location> = vec![];
+    for (line_index, line) in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "EOF" {
+            break;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let coordinates = match fields.as_slice() {
+            [_, x, y] => x.parse::<f64>().ok().zip(y.parse::<f64>().ok()),
+            _ => None,
+        };
+        match coordinates {
+            Some((x, y)) => locations.push(Location {
+                name: fields[0].to_owned(),
+                x,
+                y,
+            }),
+            None => {
+                return Err(invalid(
+                    line_index,
+                    "expected a node number and coordinates",
+                ))
+            }
+        }
+    }
+
+    match dimension {
+        Some(dimension) if dimension != locations.len() => Err(invalid_input(
+            Format::Tsplib,
+            format!(
+                "DIMENSION is {} but there are {} nodes",
+                dimension,
+                locations.len()
+            ),
+        )),
+        _ => Ok(locations),
+    }
+}
+
+fn write_tsplib(locations: &[Location]) -> Result<String, SimulationError> {
+    let mut tsplib = format!(
+        "TYPE : TSP\nDIMENSION : {}\nEDGE_WEIGHT_TYPE : EUC_2D\nNODE_COORD_SECTION\n",
+        locations.len()
+    );
+    for location in locations {
+        tsplib.push_str(&format!(
+            "{} {} {}\n",
+            node_number(location)?,
+            location.x,
+            location.y
+        ));
+    }
+    tsplib.push_str("EOF\n");
+    Ok(tsplib)
+}
+
+fn node_number(location: &Location) -> Result<usize, SimulationError> {
+    location.name.parse().map_err(|_| {
+        invalid_input(
+            Format::Tsplib,
+            format!("location {} is not a node number", location.name),
+        )
+    })
+}
+
+fn write_tsplib_tour(route: &Route) -> Result<String, SimulationError> {
+    let mut tour = format!(
+        "TYPE : TOUR\nCOMMENT : distance {}\nDIMENSION : {}\nTOUR_SECTION\n",
+        route.distance,
+        route.locations.len()
+    );
+    for location in &route.locations {
+        tour.push_str(&format!("{}\n", node_number(location)?));
+    }
+    tour.push_str("-1\nEOF\n");
+    Ok(tour)
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_tsplib() {
+        let tsplib = "NAME : square\nTYPE : TSP\nDIMENSION : 3\nEDGE_WEIGHT_TYPE : EUC_2D\n\
+                      NODE_COORD_SECTION\n1 0 0\n2 0 10.5\n3 4 0\nEOF\n";
+        let locations = read_locations(tsplib, Format::Tsplib).unwrap();
+        assert_eq!(locations.len(), 3);
+        assert_eq!(
+            locations[1],
+            Location {
+                name: "2".to_owned(),
+                x: 0.0,
+                y: 10.5
+            }
+        );
+
+        let geographic = tsplib.replace("EUC_2D", "GEO");
+        assert!(read_locations(&geographic, Format::Tsplib).is_err());
+    }
+
+    #[test]
+    fn test_route_formats() {
+        let locations: Vec<Location> = (1..=4)
+            .map(|i| Location {
+                name: i.to_string(),
+                x: i as f64,
+                y: (i * i) as f64,
+            })
+            .collect();
+        let route = Route::new(locations.clone());
+
+        let csv = write_route(&route, Format::Csv).unwrap();
+        assert!(csv.starts_with("name,x,y\n1,1.0,1.0\n"));
+        assert_eq!(read_locations(&csv, Format::Csv), Ok(locations.clone()));
+        let json = write_route(&route, Format::Json).unwrap();
+        assert_eq!(serde_json::from_str::<Route>(&json).unwrap(), route);
+        let ron = write_route(&route, Format::Ron).unwrap();
+        assert_eq!(ron::from_str::<Route>(&ron).unwrap(), route);
+        let tour = write_route(&route, Format::Tsplib).unwrap();
+        assert!(tour.contains("TOUR_SECTION\n1\n2\n3\n4\n-1\nEOF"));
+        for format in Format::ALL {
+            let path = format!("route.{}", format.route_extension());
+            assert_eq!(Format::from_path(path), Some(format));
+        }
+        assert_eq!(Format::Tsplib.route_extension(), "tour");
+        // the points of a route are its locations, in order
+        let geojson = write_route(&route, Format::GeoJson).unwrap();
+        assert!(geojson.contains("LineString"));
+        assert_eq!(read_locations(&geojson, Format::GeoJson), Ok(locations));
+    }
+
+    #[test]
+    fn test_locations_round_trip() {
+        let locations: Vec<Location> = (1..=3)
+            .map(|i| Location {
+                name: i.to_string(),
+                x: i as f64 / 3.0,
+                y: -(i as f64),
+            })
+            .collect();
+        for format in Format::ALL {
+            let text = write_locations(&locations, format).unwrap();
+            assert_eq!(
+                read_locations(&text, format),
+                Ok(locations.clone()),
+                "{}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_csv_header_detection() {
+        let location = |name: &str, x: f64, y: f64| Location {
+            name: name.to_owned(),
+            x,
+            y,
+        };
+        assert_eq!(
+            read_locations("Y, X, Name\n2, 1, A\n4, 3, B\n", Format::Csv),
+            Ok(vec![location("A", 1.0, 2.0), location("B", 3.0, 4.0)])
+        );
+        assert_eq!(
+            read_locations("A,1,2\nB,3,4\n", Format::Csv),
+            Ok(vec![location("A", 1.0, 2.0), location("B", 3.0, 4.0)])
+        );
+        assert_eq!(
+            read_locations("x,y\n1,2\n3,4\n", Format::Csv),
+            Ok(vec![location("1", 1.0, 2.0), location("2", 3.0, 4.0)])
+        );
+        assert!(read_locations("name,lat,lon\nA,1,2\n", Format::Csv).is_err());
+    }
+}
@@ -0,0 +1,358 @@
+use crate::{Location, SimulationError};
+use rand::prelude::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+// Samples of a random location before giving up on finding a point that no other location is at
+const MAX_ATTEMPTS: usize = 1000;
+
+/// Layout of the locations of a generated instance.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum Shape {
+    /// Locations spread uniformly over the square.
+    Uniform,
+    /// Locations normally distributed around cluster centres spread uniformly over the square.
+    Clustered,
+    /// Locations on a grid, with as many rows as columns or one fewer.
+    Grid,
+    /// Locations evenly spaced on the circle inscribed in the square.
+    Circle,
+    /// Half of the locations uniform, the other half clustered.
+    Mixed,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 5] = [
+        Shape::Uniform,
+        Shape::Clustered,
+        Shape::Grid,
+        Shape::Circle,
+        Shape::Mixed,
+    ];
+
+    /// Whether the instance depends on the seed and cluster settings, or only on the size.
+    pub fn is_random(&self) -> bool {
+        !matches!(self, Shape::Grid | Shape::Circle)
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Shape::Uniform => "uniform",
+            Shape::Clustered => "clustered",
+            Shape::Grid => "grid",
+            Shape::Circle => "circle",
+            Shape::Mixed => "mixed",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Shape {
+    type Err = SimulationError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Shape::ALL
+            .into_iter()
+            .find(|shape| shape.to_string().eq_ignore_ascii_case(name))
+            .ok_or_else(|| SimulationError::InvalidInput(format!("unknown shape {}", name)))
+    }
+}
+
+/// Generates instances of `size` locations in the square from (0, 0) to (`extent`, `extent`),
+/// named after their numbers and each at a different point; the same seed always generates the
+/// same instance.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct InstanceGenerator {
+    pub shape: Shape,
+    pub size: usize,
+    pub seed: u64,
+    pub extent: f64,
+    /// Number of clusters of the clustered and mixed shapes.
+    pub clusters: usize,
+    /// Standard deviation of the clustered locations around their centre, as a fraction of the
+    /// extent.
+    pub spread: f64,
+}
+
+impl InstanceGenerator {
+    pub fn new(shape: Shape, size: usize, seed: u64) -> InstanceGenerator {
+        InstanceGenerator {
+            shape,
+            size,
+            seed,
+            extent: 1000.0,
+            clusters: 5,
+            spread: 0.05,
+        }
+    }
+
+    pub fn generate(&self) -> Result<Vec<Location>, SimulationError> {
+        self.validate()?;
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut points = DistinctPoints::new(self.size);
+        match self.shape {
+            Shape::Uniform => self.uniform(self.size, &mut points, &mut rng)?,
+            Shape::Clustered => self.clustered(self.size, &mut points, &mut rng)?,
+            Shape::Grid => self.fixed(self.grid(), &mut points)?,
+            Shape::Circle => self.fixed(self.circle(), &mut points)?,
+            Shape::Mixed => {
+                self.uniform(self.size / 2, &mut points, &mut rng)?;
+                self.clustered(self.size - self.size / 2, &mut points, &mut rng)?;
+            }
+        }
+        Ok(points
+            .points
+            .into_iter()
+            .enumerate()
+            .map(|(index, (x, y))| Location {
+                name: (index + 1).to_string(),
+                x,
+                y,
+            })
+            .collect())
+    }
+
+    fn validate(&self) -> Result<(), SimulationError> {
+        let invalid = |reason: &str| Err(SimulationError::InvalidInput(reason.to_owned()));
+        if self.size == 0 {
+            invalid("an instance needs at least one location")
+        } else if !self.extent.is_finite() || self.extent <= 0.0 {
+            invalid("extent must be a positive number")
+        } else if self.clusters == 0 {
+            invalid("at least one cluster is needed")
+        } else if !self.spread.is_finite() || self.spread <= 0.0 {
+            invalid("spread must be a positive number")
+        } else if self.size as f64 > ((self.extent * 100.0).floor() + 1.0).powi(2) {
+            // the coordinates are rounded to two decimals
+            invalid("too many locations for the extent")
+        } else {
+            Ok(())
+        }
+    }
+
+    fn uniform_point<R: Rng>(&self, rng: &mut R) -> (f64, f64) {
+        (
+            rng.gen_range(0.0..=self.extent),
+            rng.gen_range(0.0..=self.extent),
+        )
+    }
+
+    fn uniform<R: Rng>(
+        &self,
+        size: usize,
+        points: &mut DistinctPoints,
+        rng: &mut R,
+    ) -> Result<(), SimulationError> {
+        (0..size).try_for_each(|_| points.sample(|| self.uniform_point(rng)))
+    }
+
+    fn clustered<R: Rng>(
+        &self,
+        size: usize,
+        points: &mut DistinctPoints,
+        rng: &mut R,
+    ) -> Result<(), SimulationError> {
+        let centres: Vec<(f64, f64)> = (0..self.clusters)
+            .map(|_| self.uniform_point(rng))
+            .collect();
+        let deviation = self.spread * self.extent;
+        (0..size).try_for_each(|index| {
+            let (x, y) = centres[index % centres.len()];
+            points.sample(|| {
+                let (dx, dy) = standard_normal_pair(rng);
+                (
+                    (x + dx * deviation).clamp(0.0, self.extent),
+                    (y + dy * deviation).clamp(0.0, self.extent),
+                )
+            })
+        })
+    }
+
+    // Adds the points of a shape that doesn't depend on the seed, which can't be sampled again
+    fn fixed(
+        &self,
+        shape_points: Vec<(f64, f64)>,
+        points: &mut DistinctPoints,
+    ) -> Result<(), SimulationError> {
+        if shape_points.into_iter().all(|point| points.insert(point)) {
+            Ok(())
+        } else {
+            Err(SimulationError::InvalidInput(format!(
+                "the extent is too small for {} {} locations",
+                self.size, self.shape
+            )))
+        }
+    }
+
+    fn grid(&self) -> Vec<(f64, f64)> {
+        let columns = (self.size as f64).sqrt().ceil() as usize;
+        let rows = self.size.div_ceil(columns);
+        let spacing = self.extent / (columns.max(rows) - 1).max(1) as f64;
+        (0..self.size)
+            .map(|index| {
+                (
+                    (index % columns) as f64 * spacing,
+                    (index / columns) as f64 * spacing,
+                )
+            })
+            .collect()
+    }
+
+    fn circle(&self) -> Vec<(f64, f64)> {
+        let radius = self.extent / 2.0;
+        (0..self.size)
+            .map(|index| {
+                let angle = 2.0 * PI * index as f64 / self.size as f64;
+                (radius + radius * angle.cos(), radius + radius * angle.sin())
+            })
+            .collect()
+    }
+}
+
+// Rounded points of an instance, kept distinct so that no two locations are at the same point
+struct DistinctPoints {
+    points: Vec<(f64, f64)>,
+    // the points in hundredths, as they are rounded to two decimals
+    taken: HashSet<(i64, i64)>,
+}
+
+impl DistinctPoints {
+    fn new(size: usize) -> Self {
+        Self {
+            points: Vec::with_capacity(size),
+            taken: HashSet::with_capacity(size),
+        }
+    }
+
+    // Adds the point once rounded, unless another point is already there
+    fn insert(&mut self, (x, y): (f64, f64)) -> bool {
+        let (x, y) = (round(x), round(y));
+        let inserted = self
+            .taken
+            .insert(((x * 100.0).round() as i64, (y * 100.0).round() as i64));
+        if inserted {
+            self.points.push((x, y));
+        }
+        inserted
+    }
+
+    // Samples points until one is not taken, since clamping and rounding make them collide
+    fn sample<F>(&mut self, mut sample_point: F) -> Result<(), SimulationError>
+    where
+        F: FnMut() -> (f64, f64),
+    {
+        if (0..MAX_ATTEMPTS).any(|_| self.insert(sample_point())) {
+            Ok(())
+        } else {
+            Err(SimulationError::InvalidInput(
+                "no room left for the locations, increase the extent or the spread".to_owned(),
+            ))
+        }
+    }
+}
+
+// Two independent samples of the standard normal distribution (Box-Muller transform)
+fn standard_normal_pair<R: Rng>(rng: &mut R) -> (f64, f64) {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    let radius = (-2.0 * u1.ln()).sqrt();
+    let angle = 2.0 * PI * u2;
+    (radius * angle.cos(), radius * angle.sin())
+}
+
+// Keeps two decimals, so that the instances read well in text
+fn round(coordinate: f64) -> f64 {
+    (coordinate * 100.0).round() / 100.0
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_shapes() {
+        for shape in Shape::ALL {
+            let generator = InstanceGenerator::new(shape, 50, 7);
+            let locations = generator.generate().unwrap();
+            assert_eq!(locations.len(), 50, "{}", shape);
+            assert!(locations
+                .iter()
+                .all(|location| (0.0..=generator.extent).contains(&location.x)
+                    && (0.0..=generator.extent).contains(&location.y)));
+            assert_eq!(locations[0].name, "1");
+            assert_eq!(locations, generator.generate().unwrap());
+            assert_eq!(shape.to_string().parse::<Shape>().unwrap(), shape);
+        }
+
+        let other_seed = InstanceGenerator::new(Shape::Uniform, 50, 8).generate();
+        assert_ne!(
+            other_seed.unwrap(),
+            InstanceGenerator::new(Shape::Uniform, 50, 7)
+                .generate()
+                .unwrap()
+        );
+
+        let grid = InstanceGenerator::new(Shape::Grid, 12, 0)
+            .generate()
+            .unwrap();
+        assert_eq!((grid[3].x, grid[3].y), (1000.0, 0.0));
+        assert_eq!((grid[4].x, grid[4].y), (0.0, 333.33));
+        assert_eq!((grid[11].x, grid[11].y), (1000.0, 666.67));
+    }
+
+    #[test]
+    fn test_distinct_points() {
+        // clamping to the square and rounding to two decimals would make these collide
+        for shape in Shape::ALL {
+            let generator = InstanceGenerator {
+                extent: 2.0,
+                ..InstanceGenerator::new(shape, 500, 3)
+            };
+            let locations = generator.generate().unwrap();
+            let points: HashSet<(u64, u64)> = locations
+                .iter()
+                .map(|location| (location.x.to_bits(), location.y.to_bits()))
+                .collect();
+            assert_eq!(points.len(), locations.len(), "{}", shape);
+        }
+
+        let dense_circle = InstanceGenerator {
+            extent: 0.05,
+            ..InstanceGenerator::new(Shape::Circle, 30, 0)
+        };
+        assert!(dense_circle.generate().is_err());
+    }
+
+    #[test]
+    fn test_invalid_generator() {
+        assert!(InstanceGenerator::new(Shape::Uniform, 0, 0)
+            .generate()
+            .is_err());
+        let generator = InstanceGenerator {
+            clusters: 0,
+            ..InstanceGenerator::new(Shape::Clustered, 10, 0)
+        };
+        assert!(matches!(
+            generator.generate(),
+            Err(SimulationError::InvalidInput(_))
+        ));
+        let generator = InstanceGenerator {
+            spread: 0.0,
+            ..InstanceGenerator::new(Shape::Clustered, 10, 0)
+        };
+        assert!(generator.generate().is_err());
+        let generator = InstanceGenerator {
+            extent: 1.0,
+            ..InstanceGenerator::new(Shape::Uniform, 101 * 101 + 1, 0)
+        };
+        assert!(generator.generate().is_err());
+    }
+}
@@ -1,10 +1,41 @@
+extern crate csv;
 extern crate rand;
+extern crate rand_chacha;
+extern crate rayon;
+extern crate ron;
 extern crate serde;
+extern crate serde_json;
 
-use rand::prelude::{thread_rng, Rng, SliceRandom, ThreadRng};
+mod checkpoint;
+mod control;
+mod formats;
+mod generators;
+mod render;
+mod stop_condition;
+mod validation;
+
+pub use checkpoint::{Checkpoint, GeneticCheckpoint};
+pub use control::SimulationControl;
+pub use formats::{read_locations, write_locations, write_route, Format};
+pub use generators::{InstanceGenerator, Shape};
+#[cfg(feature = "gif")]
+pub use render::render_gif;
+#[cfg(feature = "png")]
+pub use render::render_png;
+pub use render::{render_svg, ImageFormat, RenderOptions};
+pub use stop_condition::{SimulationProgress, StopCondition, StopConditionTracker};
+pub use validation::{
+    validate_locations, DuplicatePolicy, LocationDiagnostic, LocationIssue, Severity,
+    ValidatedLocations,
+};
+
+use rand::prelude::{Rng, SeedableRng, SliceRandom};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::fmt;
+use std::iter;
 
 #[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct Location {
@@ -45,6 +76,10 @@ impl Route {
     }
 }
 
+fn population_mean_distance(population: &[Route]) -> f64 {
+    population.iter().map(|route| route.distance).sum::<f64>() / population.len() as f64
+}
+
 fn locations_distance(locations: &[Location]) -> f64 {
     locations
         .windows(2)
@@ -57,65 +92,385 @@ fn locations_distance(locations: &[Location]) -> f64 {
 // -------------------------------------------------------------------------------------------------
 
 pub trait Simulation {
-    fn run<F>(&self, stop: &Arc<AtomicBool>, simulation_event_callback: F) -> Route
+    fn run<F>(
+        &self,
+        control: &SimulationControl,
+        simulation_event_callback: F,
+    ) -> Result<Route, SimulationError>
     where
         F: Fn(SimulationEvent);
+
+    /// Runs the simulation as an island, periodically exchanging routes with other islands.
+    /// Simulations that don't support migration ignore it and just run.
+    fn run_with_migration<F>(
+        &self,
+        control: &SimulationControl,
+        migration: &dyn Migration,
+        simulation_event_callback: F,
+    ) -> Result<Route, SimulationError>
+    where
+        F: Fn(SimulationEvent),
+    {
+        let _ = migration;
+        self.run(control, simulation_event_callback)
+    }
+}
+
+/// Object-safe counterpart of `Simulation`, implemented for every `Simulation`, so that different
+/// kinds of simulations can be run together as `BoxedSimulation`s.
+pub trait DynSimulation {
+    fn run_dyn(
+        &self,
+        control: &SimulationControl,
+        migration: Option<&dyn Migration>,
+        simulation_event_callback: &dyn Fn(SimulationEvent),
+    ) -> Result<Route, SimulationError>;
+}
+
+impl<S: Simulation> DynSimulation for S {
+    fn run_dyn(
+        &self,
+        control: &SimulationControl,
+        migration: Option<&dyn Migration>,
+        simulation_event_callback: &dyn Fn(SimulationEvent),
+    ) -> Result<Route, SimulationError> {
+        match migration {
+            Some(migration) => {
+                self.run_with_migration(control, migration, simulation_event_callback)
+            }
+            None => self.run(control, simulation_event_callback),
+        }
+    }
+}
+
+pub type BoxedSimulation = Box<dyn DynSimulation + Send>;
+
+impl Simulation for BoxedSimulation {
+    fn run<F>(
+        &self,
+        control: &SimulationControl,
+        simulation_event_callback: F,
+    ) -> Result<Route, SimulationError>
+    where
+        F: Fn(SimulationEvent),
+    {
+        // dispatch to the boxed simulation, not to the blanket implementation for the box
+        (**self).run_dyn(control, None, &simulation_event_callback)
+    }
+
+    fn run_with_migration<F>(
+        &self,
+        control: &SimulationControl,
+        migration: &dyn Migration,
+        simulation_event_callback: F,
+    ) -> Result<Route, SimulationError>
+    where
+        F: Fn(SimulationEvent),
+    {
+        (**self).run_dyn(control, Some(migration), &simulation_event_callback)
+    }
+}
+
+/// Connects a simulation (an island) to other simulations so they can exchange their best routes.
+pub trait Migration {
+    /// Iterations between migrations.
+    fn interval(&self) -> usize;
+
+    /// Maximum number of routes sent on each migration.
+    fn size(&self) -> usize;
+
+    /// Sends the emigrants to the connected islands and returns the immigrants received from them
+    /// since the previous migration.
+    fn migrate(&self, emigrants: &[Route]) -> Vec<Route>;
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SimulationError {
+    PopulationTooSmall {
+        population_size: usize,
+        minimum: usize,
+    },
+    InvalidIterationSettings {
+        max_iterations: usize,
+        assume_convergence: usize,
+    },
+    InvalidStopCondition(String),
+    InvalidLocations(Vec<LocationDiagnostic>),
+    NoThreads,
+    InvalidIslandModel(String),
+    InvalidPortfolio(String),
+    WorkerPanicked(String),
+    // index of the initial route that is not a permutation of the locations
+    InvalidInitialRoute(usize),
+    ThreadPool(String),
+    Network(String),
+    Checkpoint(String),
+    // locations or routes that could not be read or written
+    InvalidInput(String),
+    // route image that could not be rendered
+    Render(String),
 }
 
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::PopulationTooSmall {
+                population_size,
+                minimum,
+            } => write!(
+                f,
+                "population size must be at least {}, got {}",
+                minimum, population_size
+            ),
+            SimulationError::InvalidIterationSettings {
+                max_iterations,
+                assume_convergence,
+            } => write!(
+                f,
+                "max iterations ({}) must be greater than assume convergence ({})",
+                max_iterations, assume_convergence
+            ),
+            SimulationError::InvalidStopCondition(reason) => {
+                write!(f, "invalid stop condition: {}", reason)
+            }
+            SimulationError::InvalidLocations(diagnostics) => {
+                write!(f, "invalid locations")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n{}", diagnostic)?;
+                }
+                Ok(())
+            }
+            SimulationError::NoThreads => write!(f, "at least one thread is required"),
+            SimulationError::InvalidIslandModel(reason) => {
+                write!(f, "invalid island model: {}", reason)
+            }
+            SimulationError::InvalidPortfolio(reason) => write!(f, "invalid portfolio: {}", reason),
+            SimulationError::WorkerPanicked(message) => {
+                write!(f, "simulation worker panicked: {}", message)
+            }
+            SimulationError::InvalidInitialRoute(index) => write!(
+                f,
+                "initial route {} does not visit the simulation locations",
+                index
+            ),
+            SimulationError::ThreadPool(reason) => {
+                write!(f, "could not start the thread pool: {}", reason)
+            }
+            SimulationError::Network(reason) => write!(f, "network error: {}", reason),
+            SimulationError::Checkpoint(reason) => write!(f, "checkpoint error: {}", reason),
+            SimulationError::InvalidInput(reason) => write!(f, "invalid input: {}", reason),
+            SimulationError::Render(reason) => write!(f, "could not render the route: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
 #[derive(Debug)]
 pub struct GeneticSimulation {
     pub locations: Vec<Location>,
     pub population_size: usize,
     pub max_iterations: Option<usize>,
     pub assume_convergence: Option<usize>,
+    pub stop_condition: Option<StopCondition>,
+    /// Routes added to the initial population, e.g. to continue from a previous champion.
+    pub initial_routes: Vec<Route>,
+    /// Spreads the work of every generation over a thread pool.
+    pub parallel_evaluation: Option<ParallelEvaluation>,
+    /// Seeds the random number generator, so that runs can be reproduced.
+    pub seed: Option<u64>,
+    /// Iterations between `Checkpoint` events; a last one is sent when the simulation ends.
+    pub checkpoint_interval: Option<usize>,
+    /// Resumes the simulation from a checkpoint instead of a random population.
+    pub checkpoint: Option<GeneticCheckpoint>,
+}
+
+/// Evaluates the offspring of each generation of a `GeneticSimulation` on a thread pool, so that a
+/// single large population can use all the cores.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ParallelEvaluation {
+    /// Threads of the pool, or 0 for one per core.
+    pub threads: usize,
+    /// Also breed the offspring (crossover and mutation) on the pool, not only evaluate them.
+    pub parallel_breeding: bool,
+}
+
+impl ParallelEvaluation {
+    fn thread_pool(&self) -> Result<ThreadPool, SimulationError> {
+        ThreadPoolBuilder::new()
+            .num_threads(self.threads)
+            .build()
+            .map_err(|error| SimulationError::ThreadPool(error.to_string()))
+    }
 }
 
 #[derive(Debug)]
 pub enum SimulationEvent {
     Started,
     Iteration(usize),
+    Evaluations(usize),
     NewChampion(Route, usize),
+    MeanDistance(f64, usize),
+    // (from island, to island, number of routes)
+    Migration(usize, usize, usize),
+    // (worker, panic or error message)
+    WorkerFailed(usize, String),
+    // standings of the configurations of a portfolio race, best first
+    Leaderboard(Vec<PortfolioStanding>),
+    // progress of every thread of a parallel simulation, by thread index
+    WorkerStatistics(Vec<WorkerStatistics>),
+    Checkpoint(Checkpoint),
+    // the condition that stopped the simulation, of those combined by its stop condition
+    StopConditionMet(StopCondition),
     Finished,
 }
 
+/// Progress of one thread of a parallel simulation.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct WorkerStatistics {
+    pub worker: usize,
+    /// Iterations of the thread, including the runs that failed.
+    pub iterations: usize,
+    /// Evaluations of the thread, including the runs that failed.
+    pub evaluations: usize,
+    /// Distance of the best route found by the thread, if any.
+    pub best_distance: Option<f64>,
+    pub mean_distance: Option<f64>,
+    /// Number of times the thread improved the overall champion.
+    pub champions: usize,
+    /// Whether the thread found the current overall champion.
+    pub holds_champion: bool,
+    pub restarts: usize,
+    pub running: bool,
+}
+
+/// Progress of one configuration of a portfolio race.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PortfolioStanding {
+    /// Index of the configuration in the portfolio.
+    pub configuration: usize,
+    pub name: String,
+    /// Distance of the best route found by the configuration, if any.
+    pub champion_distance: Option<f64>,
+    /// Iterations of all the threads that ran the configuration.
+    pub iterations: usize,
+    /// Threads currently running the configuration.
+    pub threads: usize,
+    pub eliminated: bool,
+}
+
 impl Simulation for GeneticSimulation {
-    fn run<F>(&self, stop: &Arc<AtomicBool>, simulation_event_callback: F) -> Route
+    fn run<F>(
+        &self,
+        control: &SimulationControl,
+        simulation_event_callback: F,
+    ) -> Result<Route, SimulationError>
     where
         F: Fn(SimulationEvent),
     {
-        assert!(self.population_size > GeneticSimulation::MATING_POOL_SIZE);
-        assert!(
-            self.max_iterations.is_none()
-                || self.assume_convergence.is_none()
-                || self.max_iterations.unwrap() > self.assume_convergence.unwrap()
-        );
+        self.run_island(control, None, simulation_event_callback)
+    }
+
+    // Exchanges the best routes of the mating pool with the other islands
+    fn run_with_migration<F>(
+        &self,
+        control: &SimulationControl,
+        migration: &dyn Migration,
+        simulation_event_callback: F,
+    ) -> Result<Route, SimulationError>
+    where
+        F: Fn(SimulationEvent),
+    {
+        self.run_island(control, Some(migration), simulation_event_callback)
+    }
+}
+
+impl GeneticSimulation {
+    const MATING_POOL_SIZE: usize = 7;
+
+    fn run_island<F>(
+        &self,
+        control: &SimulationControl,
+        migration: Option<&dyn Migration>,
+        simulation_event_callback: F,
+    ) -> Result<Route, SimulationError>
+    where
+        F: Fn(SimulationEvent),
+    {
+        self.validate()?;
 
         simulation_event_callback(SimulationEvent::Started);
 
         if self.locations.len() <= 2 {
             let champion = Route::new(self.locations.clone());
             simulation_event_callback(SimulationEvent::NewChampion(champion.to_owned(), 0));
-            return champion;
+            simulation_event_callback(SimulationEvent::Finished);
+            return Ok(champion);
         }
 
-        let mut rng = thread_rng();
-
-        let mut population = self.initial_random_population(&mut rng);
-        let mut mating_pool = GeneticSimulation::allocate_mating_pool(&population);
-        GeneticSimulation::select_mating_pool(&population, &mut mating_pool);
+        let thread_pool = self
+            .parallel_evaluation
+            .map(|parallel_evaluation| parallel_evaluation.thread_pool())
+            .transpose()?;
 
-        let mut champion = mating_pool[0].to_owned();
-        let mut champion_iterations: usize = 0;
-        simulation_event_callback(SimulationEvent::NewChampion(champion.to_owned(), 0));
+        let GeneticCheckpoint {
+            mut population,
+            mut mating_pool,
+            mut champion,
+            mut iteration,
+            mut champion_iterations,
+            mut evaluations,
+            mut rng,
+        } = match &self.checkpoint {
+            Some(checkpoint) => {
+                let mut checkpoint = checkpoint.clone();
+                GeneticSimulation::select_mating_pool(
+                    &self.initial_routes,
+                    &mut checkpoint.mating_pool,
+                );
+                checkpoint
+            }
+            None => self.initial_state(),
+        };
+        simulation_event_callback(SimulationEvent::NewChampion(champion.to_owned(), iteration));
 
         let max_iterations = self.max_iterations.unwrap_or(usize::MAX);
         let assume_convergence = self.assume_convergence.unwrap_or(usize::MAX);
-        let mut iteration: usize = 0;
+        let mut stop_condition = self.stop_condition.as_ref().map(StopConditionTracker::new);
+        let checkpoint = |population: &[Route],
+                          mating_pool: &[Route],
+                          champion: &Route,
+                          iteration: usize,
+                          champion_iterations: usize,
+                          evaluations: usize,
+                          rng: &ChaCha8Rng| {
+            SimulationEvent::Checkpoint(Checkpoint::Genetic(Box::new(GeneticCheckpoint {
+                population: population.to_vec(),
+                mating_pool: mating_pool.to_vec(),
+                champion: champion.clone(),
+                iteration,
+                champion_iterations,
+                evaluations,
+                rng: rng.clone(),
+            })))
+        };
         loop {
             iteration += 1;
             champion_iterations += 1;
-            self.next_generation(&mut population, &mating_pool, &mut rng);
+            evaluations += self.next_generation(
+                &mut population,
+                &mating_pool,
+                &mut rng,
+                thread_pool.as_ref(),
+            );
             GeneticSimulation::select_mating_pool(&population, &mut mating_pool);
+            if let Some(migration) = migration {
+                if migration.interval() > 0 && iteration.is_multiple_of(migration.interval()) {
+                    let emigrants = &mating_pool[..migration.size().min(mating_pool.len())];
+                    let immigrants = migration.migrate(emigrants);
+                    GeneticSimulation::select_mating_pool(&immigrants, &mut mating_pool);
+                }
+            }
             if champion.distance > mating_pool[0].distance {
                 champion = mating_pool[0].to_owned();
                 champion_iterations = 0;
@@ -124,24 +479,90 @@ impl Simulation for GeneticSimulation {
                     iteration,
                 ));
             }
-            if iteration % 1000 == 0 {
+            if iteration.is_multiple_of(1000) {
                 simulation_event_callback(SimulationEvent::Iteration(iteration));
+                simulation_event_callback(SimulationEvent::Evaluations(evaluations));
+                simulation_event_callback(SimulationEvent::MeanDistance(
+                    population_mean_distance(&population),
+                    iteration,
+                ));
+            }
+            if self
+                .checkpoint_interval
+                .is_some_and(|interval| iteration.is_multiple_of(interval))
+            {
+                simulation_event_callback(checkpoint(
+                    &population,
+                    &mating_pool,
+                    &champion,
+                    iteration,
+                    champion_iterations,
+                    evaluations,
+                    &rng,
+                ));
+            }
+            if let (Some(paused), Some(stop_condition)) =
+                (control.wait_while_paused(), &mut stop_condition)
+            {
+                stop_condition.exclude(paused);
             }
-            if stop.load(Ordering::Relaxed)
+            if control.is_stopped()
                 || (self.max_iterations.is_some() && iteration >= max_iterations)
                 || (self.assume_convergence.is_some() && champion_iterations >= assume_convergence)
             {
                 break;
             }
+            if let Some(met_condition) = stop_condition.as_mut().and_then(|stop_condition| {
+                stop_condition.met_condition(&SimulationProgress {
+                    iteration,
+                    champion_iterations,
+                    evaluations,
+                    champion_distance: champion.distance,
+                })
+            }) {
+                simulation_event_callback(SimulationEvent::StopConditionMet(met_condition.clone()));
+                break;
+            }
         }
 
+        if self
+            .checkpoint_interval
+            .is_some_and(|interval| !iteration.is_multiple_of(interval))
+        {
+            simulation_event_callback(checkpoint(
+                &population,
+                &mating_pool,
+                &champion,
+                iteration,
+                champion_iterations,
+                evaluations,
+                &rng,
+            ));
+        }
         simulation_event_callback(SimulationEvent::Finished);
-        champion
+        Ok(champion)
     }
-}
 
-impl GeneticSimulation {
-    const MATING_POOL_SIZE: usize = 7;
+    // State before the first iteration, with a random population
+    fn initial_state(&self) -> GeneticCheckpoint {
+        let mut rng = match self.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_entropy(),
+        };
+        let population = self.initial_random_population(&mut rng);
+        let mut mating_pool = GeneticSimulation::allocate_mating_pool(&population);
+        GeneticSimulation::select_mating_pool(&population, &mut mating_pool);
+
+        GeneticCheckpoint {
+            evaluations: population.len(),
+            champion: mating_pool[0].to_owned(),
+            population,
+            mating_pool,
+            iteration: 0,
+            champion_iterations: 0,
+            rng,
+        }
+    }
 
     pub fn new(locations: Vec<Location>) -> GeneticSimulation {
         GeneticSimulation {
@@ -149,64 +570,214 @@ impl GeneticSimulation {
             population_size: 200,
             max_iterations: Some(100_000),
             assume_convergence: Some(25_000),
+            stop_condition: None,
+            initial_routes: vec![],
+            parallel_evaluation: None,
+            seed: None,
+            checkpoint_interval: None,
+            checkpoint: None,
+        }
+    }
+
+    pub fn builder(locations: Vec<Location>) -> GeneticSimulationBuilder {
+        GeneticSimulationBuilder {
+            simulation: GeneticSimulation::new(locations),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        // locations at the same point are only a problem for some inputs, they are kept by
+        // `DuplicatePolicy::Rename` and are simply 0 apart in a route
+        let errors: Vec<LocationDiagnostic> =
+            validate_locations(&self.locations, DuplicatePolicy::Reject)
+                .errors()
+                .into_iter()
+                .filter(|diagnostic| diagnostic.issue != LocationIssue::DuplicatePoint)
+                .collect();
+        if !errors.is_empty() {
+            return Err(SimulationError::InvalidLocations(errors));
+        }
+        if self.population_size <= GeneticSimulation::MATING_POOL_SIZE {
+            return Err(SimulationError::PopulationTooSmall {
+                population_size: self.population_size,
+                minimum: GeneticSimulation::MATING_POOL_SIZE + 1,
+            });
+        }
+        if let (Some(max_iterations), Some(assume_convergence)) =
+            (self.max_iterations, self.assume_convergence)
+        {
+            if max_iterations <= assume_convergence {
+                return Err(SimulationError::InvalidIterationSettings {
+                    max_iterations,
+                    assume_convergence,
+                });
+            }
         }
+        if let Some(stop_condition) = &self.stop_condition {
+            stop_condition.validate()?;
+        }
+        for (index, route) in self.initial_routes.iter().enumerate() {
+            if !self.visits_locations(route) {
+                return Err(SimulationError::InvalidInitialRoute(index));
+            }
+        }
+        if let Some(checkpoint) = &self.checkpoint {
+            self.validate_checkpoint(checkpoint)?;
+        }
+        Ok(())
     }
 
-    fn initial_random_population(&self, rng: &mut ThreadRng) -> Vec<Route> {
+    // Whether the route is a permutation of the locations, which are known to be unique
+    fn visits_locations(&self, route: &Route) -> bool {
+        route.locations.len() == self.locations.len()
+            && self
+                .locations
+                .iter()
+                .all(|location| route.locations.contains(location))
+    }
+
+    // A checkpoint saved by a simulation of other locations or population size can't be resumed
+    fn validate_checkpoint(&self, checkpoint: &GeneticCheckpoint) -> Result<(), SimulationError> {
+        let invalid = |reason: String| Err(SimulationError::Checkpoint(reason));
+        if checkpoint.population.len() != self.population_size {
+            return invalid(format!(
+                "population of {} routes, the simulation has {}",
+                checkpoint.population.len(),
+                self.population_size
+            ));
+        }
+        if checkpoint.mating_pool.len() != GeneticSimulation::MATING_POOL_SIZE {
+            return invalid(format!(
+                "mating pool of {} routes, expected {}",
+                checkpoint.mating_pool.len(),
+                GeneticSimulation::MATING_POOL_SIZE
+            ));
+        }
+        if !checkpoint
+            .population
+            .iter()
+            .chain(&checkpoint.mating_pool)
+            .chain(iter::once(&checkpoint.champion))
+            .all(|route| self.visits_locations(route))
+        {
+            return invalid("its routes don't visit the locations of the simulation".to_owned());
+        }
+        Ok(())
+    }
+
+    fn initial_random_population(&self, rng: &mut ChaCha8Rng) -> Vec<Route> {
         let mut population = Vec::<Route>::with_capacity(self.population_size);
+        population.extend(
+            self.initial_routes
+                .iter()
+                .take(self.population_size)
+                .cloned(),
+        );
         population.resize_with(self.population_size, || {
             Route::randomized(self.locations.to_owned(), rng)
         });
         population
     }
 
+    // Returns the number of routes evaluated while breeding the new generation
     fn next_generation(
         &self,
         population: &mut Vec<Route>,
         mating_pool: &[Route],
-        rng: &mut ThreadRng,
-    ) {
+        rng: &mut ChaCha8Rng,
+        thread_pool: Option<&ThreadPool>,
+    ) -> usize {
         population.clear();
 
         for i in 0..self.population_size / 5 {
             population.push(mating_pool[i % 2].clone());
         }
-        self.mutate(population, 0.0, rng);
+        let mut evaluations = self.mutate(population, 0.0, rng, thread_pool);
 
-        self.crossover(population, mating_pool, rng);
+        evaluations += self.crossover(population, mating_pool, rng, thread_pool);
 
         let mutation_threshold_distance = mating_pool[mating_pool.len() - 1].distance;
-        self.mutate(population, mutation_threshold_distance, rng);
+        evaluations += self.mutate(population, mutation_threshold_distance, rng, thread_pool);
 
         // add mating pool back to the population (the only survivors from the previous generation)
         for route in mating_pool {
             population.push(route.clone());
         }
+
+        evaluations
     }
 
-    fn crossover(&self, population: &mut Vec<Route>, mating_pool: &[Route], rng: &mut ThreadRng) {
+    fn parallel_breeding(&self) -> bool {
+        self.parallel_evaluation
+            .is_some_and(|parallel_evaluation| parallel_evaluation.parallel_breeding)
+    }
+
+    fn crossover(
+        &self,
+        population: &mut Vec<Route>,
+        mating_pool: &[Route],
+        rng: &mut ChaCha8Rng,
+        thread_pool: Option<&ThreadPool>,
+    ) -> usize {
         let children_count = self.population_size - mating_pool.len();
-        let mut shuffling_mating_pool = mating_pool.to_owned();
+        // at least one child is always born
+        let couples_count = children_count.saturating_sub(population.len()).max(1);
+        let couples = GeneticSimulation::couples(mating_pool.len(), couples_count, rng);
+        let mate = |&(x, y): &(usize, usize), rng: &mut ChaCha8Rng| {
+            GeneticSimulation::mate(&mating_pool[x], &mating_pool[y], rng)
+        };
 
-        'mating: loop {
-            let children = shuffling_mating_pool
-                .windows(2)
-                .map(|couple| GeneticSimulation::mate(couple, rng));
+        let children: Vec<Route> = match thread_pool {
+            None => couples
+                .iter()
+                .map(|couple| Route::new(mate(couple, rng)))
+                .collect(),
+            Some(thread_pool) if self.parallel_breeding() => {
+                // every child gets its own generator, seeded from the simulation one, so that
+                // seeded runs remain reproducible
+                let seeds: Vec<u64> = couples.iter().map(|_| rng.gen()).collect();
+                thread_pool.install(|| {
+                    couples
+                        .par_iter()
+                        .zip(seeds.par_iter())
+                        .map(|(couple, seed)| {
+                            Route::new(mate(couple, &mut ChaCha8Rng::seed_from_u64(*seed)))
+                        })
+                        .collect()
+                })
+            }
+            Some(thread_pool) => {
+                let offsprings: Vec<Vec<Location>> =
+                    couples.iter().map(|couple| mate(couple, rng)).collect();
+                thread_pool.install(|| offsprings.into_par_iter().map(Route::new).collect())
+            }
+        };
+
+        population.extend(children);
+        couples.len()
+    }
+
+    // Pairs of mating pool indices, neighbours in the mating pool order, which is shuffled every
+    // time all of them have mated
+    fn couples(mating_pool_len: usize, count: usize, rng: &mut ChaCha8Rng) -> Vec<(usize, usize)> {
+        let mut order: Vec<usize> = (0..mating_pool_len).collect();
+        let mut couples = Vec::<(usize, usize)>::with_capacity(count);
 
-            for child in children {
-                population.push(child);
-                if population.len() >= children_count {
-                    break 'mating;
+        loop {
+            for couple in order.windows(2) {
+                couples.push((couple[0], couple[1]));
+                if couples.len() >= count {
+                    return couples;
                 }
             }
 
-            shuffling_mating_pool.shuffle(rng);
+            order.shuffle(rng);
         }
     }
 
-    fn mate(couple: &[Route], rng: &mut ThreadRng) -> Route {
-        let parent_x = &couple[0].locations;
-        let parent_y = &couple[1].locations;
+    fn mate(parent_x: &Route, parent_y: &Route, rng: &mut ChaCha8Rng) -> Vec<Location> {
+        let parent_x = &parent_x.locations;
+        let parent_y = &parent_y.locations;
         let length = parent_x.len();
         let mut offspring = Vec::<Location>::with_capacity(length);
 
@@ -249,46 +820,83 @@ impl GeneticSimulation {
                 recombined = true;
             }
         }
-        Route::new(offspring)
+        offspring
     }
 
     fn mutate(
         &self,
         population: &mut [Route],
         mutation_threshold_distance: f64,
-        rng: &mut ThreadRng,
-    ) {
-        let route_length = self.locations.len();
-
-        let single_mutation_swaps = 1;
-        let small_mutation_swaps = ((route_length + 1) / 6).max(1);
-        let medium_mutation_swaps = ((route_length + 1) / 4).max(2);
-        let big_mutation_swaps = ((route_length + 1) / 2).max(3);
-
-        for route in population {
-            if route.distance > mutation_threshold_distance {
-                if rng.gen_bool(0.667) {
-                    // highest-chance of single mutation
-                    GeneticSimulation::swap_genes(single_mutation_swaps, route, route_length, rng);
-                    route.distance = locations_distance(&route.locations);
-                } else if rng.gen_bool(0.667) {
-                    // high-chance of small mutation
-                    GeneticSimulation::swap_genes(small_mutation_swaps, route, route_length, rng);
-                    route.distance = locations_distance(&route.locations);
-                } else if rng.gen_bool(0.667) {
-                    // smaller chance of bigger mutation
-                    GeneticSimulation::swap_genes(medium_mutation_swaps, route, route_length, rng);
-                    route.distance = locations_distance(&route.locations);
-                } else {
-                    // yet smaller chance of yet bigger mutation
-                    GeneticSimulation::swap_genes(big_mutation_swaps, route, route_length, rng);
-                    route.distance = locations_distance(&route.locations);
+        rng: &mut ChaCha8Rng,
+        thread_pool: Option<&ThreadPool>,
+    ) -> usize {
+        match thread_pool {
+            None => {
+                let mut mutations = 0;
+                for route in population {
+                    if route.distance > mutation_threshold_distance {
+                        mutations += 1;
+                        self.mutate_route(route, rng);
+                        route.distance = locations_distance(&route.locations);
+                    }
+                }
+                mutations
+            }
+            Some(thread_pool) if self.parallel_breeding() => {
+                let seeds: Vec<u64> = population.iter().map(|_| rng.gen()).collect();
+                thread_pool.install(|| {
+                    population
+                        .par_iter_mut()
+                        .zip(seeds.par_iter())
+                        .filter(|(route, _)| route.distance > mutation_threshold_distance)
+                        .map(|(route, seed)| {
+                            self.mutate_route(route, &mut ChaCha8Rng::seed_from_u64(*seed));
+                            route.distance = locations_distance(&route.locations);
+                        })
+                        .count()
+                })
+            }
+            Some(thread_pool) => {
+                let mut mutated = vec![false; population.len()];
+                for (route, mutated) in population.iter_mut().zip(mutated.iter_mut()) {
+                    if route.distance > mutation_threshold_distance {
+                        self.mutate_route(route, rng);
+                        *mutated = true;
+                    }
                 }
+                thread_pool.install(|| {
+                    population
+                        .par_iter_mut()
+                        .zip(mutated.par_iter())
+                        .filter(|(_, mutated)| **mutated)
+                        .map(|(route, _)| route.distance = locations_distance(&route.locations))
+                        .count()
+                })
             }
         }
     }
 
-    fn swap_genes(n: usize, route: &mut Route, route_length: usize, rng: &mut ThreadRng) {
+    // Swaps genes of the route, without updating its distance
+    fn mutate_route(&self, route: &mut Route, rng: &mut ChaCha8Rng) {
+        let route_length = self.locations.len();
+
+        let swaps = if rng.gen_bool(0.667) {
+            // highest-chance of single mutation
+            1
+        } else if rng.gen_bool(0.667) {
+            // high-chance of small mutation
+            ((route_length + 1) / 6).max(1)
+        } else if rng.gen_bool(0.667) {
+            // smaller chance of bigger mutation
+            ((route_length + 1) / 4).max(2)
+        } else {
+            // yet smaller chance of yet bigger mutation
+            route_length.div_ceil(2).max(3)
+        };
+        GeneticSimulation::swap_genes(swaps, route, route_length, rng);
+    }
+
+    fn swap_genes(n: usize, route: &mut Route, route_length: usize, rng: &mut ChaCha8Rng) {
         for _ in 0..n {
             let i1 = rng.gen_range(0..route_length);
             let i2 = rng.gen_range(0..route_length);
@@ -345,11 +953,64 @@ impl GeneticSimulation {
     }
 }
 
+/// Builds a `GeneticSimulation`, validating its configuration.
+pub struct GeneticSimulationBuilder {
+    simulation: GeneticSimulation,
+}
+
+impl GeneticSimulationBuilder {
+    pub fn population_size(mut self, population_size: usize) -> Self {
+        self.simulation.population_size = population_size;
+        self
+    }
+
+    pub fn max_iterations(mut self, max_iterations: Option<usize>) -> Self {
+        self.simulation.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn assume_convergence(mut self, assume_convergence: Option<usize>) -> Self {
+        self.simulation.assume_convergence = assume_convergence;
+        self
+    }
+
+    pub fn stop_condition(mut self, stop_condition: Option<StopCondition>) -> Self {
+        self.simulation.stop_condition = stop_condition;
+        self
+    }
+
+    pub fn parallel_evaluation(mut self, parallel_evaluation: Option<ParallelEvaluation>) -> Self {
+        self.simulation.parallel_evaluation = parallel_evaluation;
+        self
+    }
+
+    pub fn seed(mut self, seed: Option<u64>) -> Self {
+        self.simulation.seed = seed;
+        self
+    }
+
+    pub fn checkpoint_interval(mut self, checkpoint_interval: Option<usize>) -> Self {
+        self.simulation.checkpoint_interval = checkpoint_interval;
+        self
+    }
+
+    pub fn checkpoint(mut self, checkpoint: Option<GeneticCheckpoint>) -> Self {
+        self.simulation.checkpoint = checkpoint;
+        self
+    }
+
+    pub fn build(self) -> Result<GeneticSimulation, SimulationError> {
+        self.simulation.validate()?;
+        Ok(self.simulation)
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Mutex;
 
     #[test]
     fn test_simulate_2_locations() {
@@ -367,7 +1028,169 @@ mod tests {
         ];
 
         let simulation = GeneticSimulation::new(locations.to_owned());
-        let solution = simulation.run(&Arc::new(AtomicBool::default()), |_| {});
-        assert_eq!(solution, Route::new(locations))
+        let solution = simulation.run(&SimulationControl::new(), |_| {});
+        assert_eq!(solution, Ok(Route::new(locations)))
+    }
+
+    #[test]
+    fn test_simulate_renamed_duplicate_points() {
+        // the last two are at the points of the first two, and one of them shares its name
+        let locations: Vec<Location> = [
+            ("A", 0.0, 0.0),
+            ("B", 3.0, 0.0),
+            ("C", 3.0, 4.0),
+            ("D", 0.0, 4.0),
+            ("E", 1.0, 2.0),
+            ("A", 0.0, 0.0),
+            ("F", 3.0, 0.0),
+        ]
+        .into_iter()
+        .map(|(name, x, y)| Location {
+            name: name.to_owned(),
+            x,
+            y,
+        })
+        .collect();
+        let validated = validate_locations(&locations, DuplicatePolicy::Rename);
+        assert!(!validated.has_errors());
+        assert_eq!(validated.locations.len(), 7);
+
+        let simulation = GeneticSimulation::builder(validated.locations.clone())
+            .max_iterations(Some(100))
+            .assume_convergence(None)
+            .build()
+            .unwrap();
+        let route = simulation.run(&SimulationControl::new(), |_| {}).unwrap();
+        assert_eq!(route.locations.len(), 7);
+    }
+
+    #[test]
+    fn test_builder_rejects_small_population() {
+        let simulation = GeneticSimulation::builder(vec![])
+            .population_size(5)
+            .build();
+        assert_eq!(
+            simulation.err(),
+            Some(SimulationError::PopulationTooSmall {
+                population_size: 5,
+                minimum: 8
+            })
+        )
+    }
+
+    #[test]
+    fn test_parallel_evaluation() {
+        let locations: Vec<Location> = InstanceGenerator::new(Shape::Grid, 15, 0)
+            .generate()
+            .unwrap();
+
+        for parallel_breeding in [false, true] {
+            let simulation = GeneticSimulation::builder(locations.clone())
+                .max_iterations(Some(100))
+                .assume_convergence(None)
+                .parallel_evaluation(Some(ParallelEvaluation {
+                    threads: 2,
+                    parallel_breeding,
+                }))
+                .build()
+                .unwrap();
+
+            let route = simulation.run(&SimulationControl::new(), |_| {}).unwrap();
+            assert_eq!(route.locations.len(), locations.len());
+            assert!(locations
+                .iter()
+                .all(|location| route.locations.contains(location)));
+            assert_eq!(route.distance, locations_distance(&route.locations));
+        }
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let locations: Vec<Location> = InstanceGenerator::new(Shape::Grid, 15, 0)
+            .generate()
+            .unwrap();
+        let simulation = GeneticSimulation::builder(locations)
+            .max_iterations(Some(200))
+            .assume_convergence(None)
+            .seed(Some(7))
+            .checkpoint_interval(Some(100))
+            .build()
+            .unwrap();
+        let run = |simulation: &GeneticSimulation| {
+            let checkpoints = Mutex::new(Vec::<GeneticCheckpoint>::new());
+            let route = simulation
+                .run(&SimulationControl::new(), |simulation_event| {
+                    if let SimulationEvent::Checkpoint(Checkpoint::Genetic(checkpoint)) =
+                        simulation_event
+                    {
+                        checkpoints.lock().unwrap().push(*checkpoint);
+                    }
+                })
+                .unwrap();
+            (route, checkpoints.into_inner().unwrap())
+        };
+
+        let (route, checkpoints) = run(&simulation);
+        assert_eq!(checkpoints.len(), 2);
+        assert_eq!(checkpoints[0].iteration, 100);
+        let checkpoint = Checkpoint::Genetic(Box::new(checkpoints[0].clone()));
+        let serialized = ron::to_string(&checkpoint).unwrap();
+        assert_eq!(ron::from_str::<Checkpoint>(&serialized), Ok(checkpoint));
+
+        let resumed = GeneticSimulation {
+            checkpoint: Some(checkpoints[0].clone()),
+            ..simulation
+        };
+        let (resumed_route, resumed_checkpoints) = run(&resumed);
+        assert_eq!(resumed_route, route);
+        assert_eq!(resumed_checkpoints, checkpoints[1..]);
+    }
+
+    #[test]
+    fn test_resume_from_mismatched_checkpoint() {
+        let locations: Vec<Location> = InstanceGenerator::new(Shape::Grid, 15, 0)
+            .generate()
+            .unwrap();
+        let resume =
+            |locations: &[Location], population_size: usize, checkpoint: &GeneticCheckpoint| {
+                GeneticSimulation::builder(locations.to_vec())
+                    .population_size(population_size)
+                    .checkpoint(Some(checkpoint.clone()))
+                    .build()
+                    .and_then(|simulation| simulation.run(&SimulationControl::new(), |_| {}))
+            };
+        let checkpoint = GeneticSimulation::builder(locations.clone())
+            .population_size(50)
+            .seed(Some(7))
+            .build()
+            .unwrap()
+            .initial_state();
+
+        assert!(matches!(
+            resume(&locations, 60, &checkpoint),
+            Err(SimulationError::Checkpoint(_))
+        ));
+
+        let mut short_mating_pool = checkpoint.clone();
+        short_mating_pool.mating_pool.truncate(3);
+        assert!(matches!(
+            resume(&locations, 50, &short_mating_pool),
+            Err(SimulationError::Checkpoint(_))
+        ));
+
+        let other_locations = InstanceGenerator::new(Shape::Circle, 15, 0)
+            .generate()
+            .unwrap();
+        assert!(matches!(
+            resume(&other_locations, 50, &checkpoint),
+            Err(SimulationError::Checkpoint(_))
+        ));
+
+        let mut repeated_location = checkpoint;
+        repeated_location.population[0].locations[1] = locations[0].clone();
+        assert!(matches!(
+            resume(&locations, 50, &repeated_location),
+            Err(SimulationError::Checkpoint(_))
+        ));
     }
 }
@@ -0,0 +1,378 @@
+use crate::{Route, SimulationError};
+use std::fmt::Write;
+use std::path::Path;
+#[cfg(feature = "gif")]
+use std::time::Duration;
+
+const MARGIN: f64 = 30.0;
+const TITLE_HEIGHT: f64 = 30.0;
+const STATS_HEIGHT: f64 = 25.0;
+const POINT_RADIUS: f64 = 4.0;
+
+/// Image format of a rendered route.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ImageFormat {
+    Svg,
+    /// Rasterized SVG; needs the `png` feature.
+    Png,
+}
+
+impl ImageFormat {
+    /// Format of an image file from its extension.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<ImageFormat> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "svg" => Some(ImageFormat::Svg),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+
+    pub fn render(
+        &self,
+        route: &Route,
+        options: &RenderOptions,
+    ) -> Result<Vec<u8>, SimulationError> {
+        match self {
+            ImageFormat::Svg => Ok(render_svg(route, options).into_bytes()),
+            #[cfg(feature = "png")]
+            ImageFormat::Png => render_png(route, options),
+            #[cfg(not(feature = "png"))]
+            ImageFormat::Png => Err(SimulationError::Render(
+                "PNG images need the png feature of tsp-sim-agent".to_owned(),
+            )),
+        }
+    }
+}
+
+/// What a rendered route image shows, and its size in pixels.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RenderOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Draws the name of every location next to it.
+    pub labels: bool,
+    /// Drawn above the route.
+    pub title: Option<String>,
+    /// Draws the number of locations and the distance below the route.
+    pub stats: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            width: 800,
+            height: 600,
+            labels: true,
+            title: None,
+            stats: true,
+        }
+    }
+}
+
+/// Renders the route, scaled to fit the image with its aspect ratio kept, as an SVG document.
+///
+/// The start of the route is drawn in green and its end in blue; as on screen, y grows downwards.
+pub fn render_svg(route: &Route, options: &RenderOptions) -> String {
+    let width = options.width as f64;
+    let height = options.height as f64;
+    let title_height = if options.title.is_some() {
+        TITLE_HEIGHT
+    } else {
+        0.0
+    };
+    let stats_height = if options.stats { STATS_HEIGHT } else { 0.0 };
+    let top = MARGIN + title_height;
+    let bottom = height - MARGIN - stats_height;
+
+    // fits the bounding box of the locations in the drawing area, centred
+    let locations = &route.locations;
+    let (min_x, max_x) = bounds(locations.iter().map(|location| location.x));
+    let (min_y, max_y) = bounds(locations.iter().map(|location| location.y));
+    let area_width = (width - 2.0 * MARGIN).max(1.0);
+    let area_height = (bottom - top).max(1.0);
+    let scale = match (max_x - min_x, max_y - min_y) {
+        (dx, dy) if dx > 0.0 || dy > 0.0 => (area_width / dx).min(area_height / dy),
+        _ => 1.0,
+    };
+    let offset_x = MARGIN + (area_width - (max_x - min_x) * scale) / 2.0;
+    let offset_y = top + (area_height - (max_y - min_y) * scale) / 2.0;
+    let point = |x: f64, y: f64| {
+        (
+            offset_x + (x - min_x) * scale,
+            offset_y + (y - min_y) * scale,
+        )
+    };
+
+    let mut svg = String::new();
+    // writing to a String cannot fail
+    let _ = write_svg(&mut svg, route, options, point);
+    svg
+}
+
+fn write_svg(
+    svg: &mut String,
+    route: &Route,
+    options: &RenderOptions,
+    point: impl Fn(f64, f64) -> (f64, f64),
+) -> std::fmt::Result {
+    let width = options.width;
+    let height = options.height;
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif">"#
+    )?;
+    writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+
+    if let Some(title) = &options.title {
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-size="18" text-anchor="middle">{}</text>"#,
+            width as f64 / 2.0,
+            MARGIN + TITLE_HEIGHT / 2.0,
+            escape(title)
+        )?;
+    }
+
+    let locations = &route.locations;
+    if locations.len() > 1 {
+        let points = locations
+            .iter()
+            .map(|location| {
+                let (x, y) = point(location.x, location.y);
+                format!("{:.2},{:.2}", x, y)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            svg,
+            r##"<polyline points="{}" fill="none" stroke="#d04040" stroke-width="1.5"/>"##,
+            points
+        )?;
+    }
+
+    for (index, location) in locations.iter().enumerate() {
+        let (x, y) = point(location.x, location.y);
+        let (fill, radius) = if index == 0 {
+            ("#30a030", POINT_RADIUS * 1.5)
+        } else if index == locations.len() - 1 {
+            ("#3060d0", POINT_RADIUS * 1.5)
+        } else {
+            ("#404040", POINT_RADIUS)
+        };
+        writeln!(
+            svg,
+            r#"<circle cx="{:.2}" cy="{:.2}" r="{}" fill="{}"/>"#,
+            x, y, radius, fill
+        )?;
+        if options.labels {
+            writeln!(
+                svg,
+                r#"<text x="{:.2}" y="{:.2}" font-size="11">{}</text>"#,
+                x + POINT_RADIUS * 1.5,
+                y - POINT_RADIUS * 1.5,
+                escape(&location.name)
+            )?;
+        }
+    }
+
+    if options.stats {
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-size="13">{} locations, distance {:.3}</text>"#,
+            MARGIN,
+            height as f64 - MARGIN,
+            locations.len(),
+            route.distance
+        )?;
+    }
+    writeln!(svg, "</svg>")
+}
+
+/// Renders the route as a PNG image, rasterizing its SVG; text is drawn with the system fonts.
+#[cfg(feature = "png")]
+pub fn render_png(route: &Route, options: &RenderOptions) -> Result<Vec<u8>, SimulationError> {
+    rasterize(&render_svg(route, options), &usvg_options())?
+        .encode_png()
+        .map_err(|error| SimulationError::Render(error.to_string()))
+}
+
+/// Renders the routes as the frames of an animated GIF that loops forever, showing each route
+/// for `frame_delay`; every frame must have the size of the first.
+#[cfg(feature = "gif")]
+pub fn render_gif(
+    frames: &[(&Route, RenderOptions)],
+    frame_delay: Duration,
+) -> Result<Vec<u8>, SimulationError> {
+    let render_error = |error: &dyn std::fmt::Display| SimulationError::Render(error.to_string());
+    let (width, height) = match frames.first() {
+        Some((_, options)) => (options.width, options.height),
+        None => return Err(SimulationError::Render("no frames to animate".to_owned())),
+    };
+    let too_large =
+        || SimulationError::Render("GIF images are at most 65535 pixels wide".to_owned());
+    let gif_width = u16::try_from(width).map_err(|_| too_large())?;
+    let gif_height = u16::try_from(height).map_err(|_| too_large())?;
+    // the delay is in hundredths of a second
+    let delay = (frame_delay.as_millis() / 10).clamp(1, u16::MAX as u128) as u16;
+    // loading the system fonts takes longer than rasterizing a frame
+    let usvg_options = usvg_options();
+
+    let mut gif = vec![];
+    {
+        let mut encoder = gif::Encoder::new(&mut gif, gif_width, gif_height, &[])
+            .map_err(|error| render_error(&error))?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(|error| render_error(&error))?;
+        for (route, options) in frames {
+            if (options.width, options.height) != (width, height) {
+                return Err(SimulationError::Render(
+                    "all the frames must have the same size".to_owned(),
+                ));
+            }
+            let mut pixels = rasterize(&render_svg(route, options), &usvg_options)?.take();
+            let mut frame = gif::Frame::from_rgba_speed(gif_width, gif_height, &mut pixels, 10);
+            frame.delay = delay;
+            encoder
+                .write_frame(&frame)
+                .map_err(|error| render_error(&error))?;
+        }
+    }
+    Ok(gif)
+}
+
+// Options to parse the SVG with, and the system fonts to draw its text
+#[cfg(feature = "png")]
+fn usvg_options() -> resvg::usvg::Options<'static> {
+    let mut usvg_options = resvg::usvg::Options::default();
+    usvg_options.fontdb_mut().load_system_fonts();
+    usvg_options
+}
+
+#[cfg(feature = "png")]
+fn rasterize(
+    svg: &str,
+    usvg_options: &resvg::usvg::Options,
+) -> Result<resvg::tiny_skia::Pixmap, SimulationError> {
+    use resvg::{tiny_skia, usvg};
+
+    let tree = usvg::Tree::from_str(svg, usvg_options)
+        .map_err(|error| SimulationError::Render(error.to_string()))?;
+
+    let size = tree.size().to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| SimulationError::Render("image must not be empty".to_owned()))?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+        (min.min(value), max.max(value))
+    });
+    if min.is_finite() && max.is_finite() {
+        (min, max)
+    } else {
+        (0.0, 0.0)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+
+    fn route() -> Route {
+        let location = |name: &str, x: f64, y: f64| Location {
+            name: name.to_owned(),
+            x,
+            y,
+        };
+        Route {
+            locations: vec![
+                location("A", 0.0, 0.0),
+                location("B & C", 100.0, 0.0),
+                location("D", 100.0, 50.0),
+            ],
+            distance: 150.0,
+        }
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let options = RenderOptions {
+            title: Some("<Route>".to_owned()),
+            ..RenderOptions::default()
+        };
+        let svg = render_svg(&route(), &options);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        // the route is wider than tall, so it spans the whole width within the margins
+        assert!(svg.contains(r#"points="30.00,117.50 770.00,117.50 770.00,487.50""#));
+        assert!(svg.contains("B &amp; C"));
+        assert!(svg.contains("&lt;Route&gt;"));
+        assert!(svg.contains("3 locations, distance 150.000"));
+        assert_eq!(svg.matches("<circle").count(), 3);
+
+        let options = RenderOptions {
+            labels: false,
+            stats: false,
+            ..RenderOptions::default()
+        };
+        let svg = render_svg(&route(), &options);
+        assert!(!svg.contains("B &amp; C"));
+        assert!(!svg.contains("distance"));
+    }
+
+    #[test]
+    fn test_image_format() {
+        assert_eq!(ImageFormat::from_path("route.SVG"), Some(ImageFormat::Svg));
+        assert_eq!(ImageFormat::from_path("route.png"), Some(ImageFormat::Png));
+        assert_eq!(ImageFormat::from_path("route.csv"), None);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_render_png() {
+        let options = RenderOptions {
+            width: 200,
+            height: 100,
+            ..RenderOptions::default()
+        };
+        let png = render_png(&route(), &options).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        // the width and height are the first fields of the IHDR chunk
+        assert_eq!(&png[16..24], &[0, 0, 0, 200, 0, 0, 0, 100]);
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn test_render_gif() {
+        let options = RenderOptions {
+            width: 120,
+            height: 80,
+            ..RenderOptions::default()
+        };
+        let mut reversed = route();
+        reversed.locations.reverse();
+        let frames = [(&route(), options.clone()), (&reversed, options)];
+        let gif = render_gif(&frames, Duration::from_millis(500)).unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+        assert_eq!(&gif[6..10], &[120, 0, 80, 0]);
+
+        let other_size = RenderOptions::default();
+        let frames = [(&route(), frames[0].1.clone()), (&reversed, other_size)];
+        assert!(render_gif(&frames, Duration::from_millis(500)).is_err());
+        assert!(render_gif(&[], Duration::from_millis(500)).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::SimulationError;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Composable termination criteria for a `Simulation`.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum StopCondition {
+    /// Stop after the given number of iterations.
+    MaxIterations(usize),
+    /// Stop when the champion has not changed for the given number of iterations.
+    NoImprovement(usize),
+    /// Stop when the wall-clock time since the simulation started exceeds the limit.
+    TimeLimit(Duration),
+    /// Stop when the champion distance is at or below the target.
+    TargetDistance(f64),
+    /// Stop after the given number of route (fitness) evaluations.
+    MaxEvaluations(usize),
+    /// Stop when the relative improvement of the champion distance over the last `window`
+    /// iterations is below `threshold` (e.g. `0.001` for 0.1%).
+    ImprovementBelow { window: usize, threshold: f64 },
+    /// Stop when any of the conditions is met.
+    Any(Vec<StopCondition>),
+    /// Stop when all of the conditions are met.
+    All(Vec<StopCondition>),
+}
+
+/// Snapshot of the simulation state used to evaluate a `StopCondition`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SimulationProgress {
+    pub iteration: usize,
+    pub champion_iterations: usize,
+    pub evaluations: usize,
+    pub champion_distance: f64,
+}
+
+impl StopCondition {
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let invalid = |reason: &str| Err(SimulationError::InvalidStopCondition(reason.to_owned()));
+        match self {
+            StopCondition::TargetDistance(target) if !target.is_finite() => {
+                invalid("target distance must be a finite number")
+            }
+            StopCondition::ImprovementBelow { window: 0, .. } => {
+                invalid("improvement window must be at least one iteration")
+            }
+            StopCondition::ImprovementBelow { threshold, .. }
+                if !threshold.is_finite() || *threshold < 0.0 =>
+            {
+                invalid("improvement threshold must be a non-negative number")
+            }
+            StopCondition::Any(conditions) | StopCondition::All(conditions) => {
+                if conditions.is_empty() {
+                    invalid("at least one condition must be combined")
+                } else {
+                    conditions.iter().try_for_each(StopCondition::validate)
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn max_window(&self) -> usize {
+        match self {
+            StopCondition::ImprovementBelow { window, .. } => *window,
+            StopCondition::Any(conditions) | StopCondition::All(conditions) => conditions
+                .iter()
+                .map(StopCondition::max_window)
+                .max()
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    fn is_met(&self, progress: &SimulationProgress, elapsed: Duration, history: &History) -> bool {
+        match self {
+            StopCondition::MaxIterations(max_iterations) => progress.iteration >= *max_iterations,
+            StopCondition::NoImprovement(iterations) => progress.champion_iterations >= *iterations,
+            StopCondition::TimeLimit(time_limit) => elapsed >= *time_limit,
+            StopCondition::TargetDistance(target) => progress.champion_distance <= *target,
+            StopCondition::MaxEvaluations(max_evaluations) => {
+                progress.evaluations >= *max_evaluations
+            }
+            StopCondition::ImprovementBelow { window, threshold } => history
+                .distance_at(progress.iteration.saturating_sub(*window))
+                .filter(|_| progress.iteration >= *window)
+                .is_some_and(|past_distance| {
+                    // a route of coincident locations can't improve any further
+                    past_distance <= 0.0
+                        || (past_distance - progress.champion_distance) / past_distance < *threshold
+                }),
+            StopCondition::Any(_) | StopCondition::All(_) => {
+                self.met_condition(progress, elapsed, history).is_some()
+            }
+        }
+    }
+
+    // The condition that stops the simulation: the first one met of those combined by `Any`, or
+    // the condition itself
+    fn met_condition(
+        &self,
+        progress: &SimulationProgress,
+        elapsed: Duration,
+        history: &History,
+    ) -> Option<&StopCondition> {
+        match self {
+            StopCondition::Any(conditions) => conditions
+                .iter()
+                .find_map(|condition| condition.met_condition(progress, elapsed, history)),
+            StopCondition::All(conditions) => (!conditions.is_empty()
+                && conditions
+                    .iter()
+                    .all(|condition| condition.is_met(progress, elapsed, history)))
+            .then_some(self),
+            _ => self.is_met(progress, elapsed, history).then_some(self),
+        }
+    }
+}
+
+// Champion distances by iteration, kept only as far back as the largest improvement window
+struct History {
+    window: usize,
+    distances: VecDeque<(usize, f64)>,
+}
+
+impl History {
+    fn record(&mut self, iteration: usize, distance: f64) {
+        match self.distances.back_mut() {
+            Some(last) if last.0 == iteration => last.1 = distance,
+            _ => self.distances.push_back((iteration, distance)),
+        }
+        let oldest_needed = iteration.saturating_sub(self.window);
+        while self.distances.len() > 1 && self.distances[1].0 <= oldest_needed {
+            self.distances.pop_front();
+        }
+    }
+
+    // Champion distance as it was at the given iteration
+    fn distance_at(&self, iteration: usize) -> Option<f64> {
+        let index = self
+            .distances
+            .partition_point(|(recorded_iteration, _)| *recorded_iteration <= iteration);
+        index.checked_sub(1).map(|index| self.distances[index].1)
+    }
+}
+
+/// Evaluates a `StopCondition` as a simulation progresses, keeping track of the start time and
+/// of the champion history required by `StopCondition::ImprovementBelow`.
+pub struct StopConditionTracker<'a> {
+    condition: &'a StopCondition,
+    start_time: Instant,
+    history: History,
+}
+
+impl<'a> StopConditionTracker<'a> {
+    pub fn new(condition: &'a StopCondition) -> Self {
+        Self {
+            condition,
+            start_time: Instant::now(),
+            history: History {
+                window: condition.max_window(),
+                distances: VecDeque::new(),
+            },
+        }
+    }
+
+    /// Excludes time the simulation spent paused from `StopCondition::TimeLimit`.
+    pub fn exclude(&mut self, paused: Duration) {
+        self.start_time += paused;
+    }
+
+    pub fn is_met(&mut self, progress: &SimulationProgress) -> bool {
+        self.met_condition(progress).is_some()
+    }
+
+    /// The condition that stops the simulation, if met: the first one met of those combined by
+    /// `StopCondition::Any`, or else the whole condition.
+    pub fn met_condition(&mut self, progress: &SimulationProgress) -> Option<&'a StopCondition> {
+        if self.history.window > 0 {
+            self.history
+                .record(progress.iteration, progress.champion_distance);
+        }
+        self.condition
+            .met_condition(progress, self.start_time.elapsed(), &self.history)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(iteration: usize, champion_distance: f64) -> SimulationProgress {
+        SimulationProgress {
+            iteration,
+            champion_iterations: 0,
+            evaluations: iteration * 100,
+            champion_distance,
+        }
+    }
+
+    #[test]
+    fn test_any_and_all() {
+        let any = StopCondition::Any(vec![
+            StopCondition::MaxIterations(10),
+            StopCondition::TargetDistance(50.0),
+        ]);
+        let all = StopCondition::All(vec![
+            StopCondition::MaxIterations(10),
+            StopCondition::TargetDistance(50.0),
+        ]);
+
+        let mut any_tracker = StopConditionTracker::new(&any);
+        let mut all_tracker = StopConditionTracker::new(&all);
+        assert!(!any_tracker.is_met(&progress(5, 100.0)));
+        assert!(any_tracker.is_met(&progress(5, 40.0)));
+        assert!(!all_tracker.is_met(&progress(5, 40.0)));
+        assert!(all_tracker.is_met(&progress(10, 40.0)));
+    }
+
+    #[test]
+    fn test_met_condition() {
+        let any = StopCondition::Any(vec![
+            StopCondition::TimeLimit(Duration::from_secs(3600)),
+            StopCondition::All(vec![
+                StopCondition::MaxIterations(10),
+                StopCondition::TargetDistance(50.0),
+            ]),
+            StopCondition::TargetDistance(40.0),
+        ]);
+
+        let mut tracker = StopConditionTracker::new(&any);
+        assert_eq!(
+            tracker.met_condition(&progress(5, 40.0)),
+            Some(&StopCondition::TargetDistance(40.0))
+        );
+        assert!(matches!(
+            tracker.met_condition(&progress(10, 45.0)),
+            Some(StopCondition::All(_))
+        ));
+        assert_eq!(tracker.met_condition(&progress(10, 60.0)), None);
+    }
+
+    #[test]
+    fn test_improvement_below() {
+        let condition = StopCondition::ImprovementBelow {
+            window: 100,
+            threshold: 0.01,
+        };
+
+        let mut tracker = StopConditionTracker::new(&condition);
+        assert!(!tracker.is_met(&progress(0, 200.0)));
+        assert!(!tracker.is_met(&progress(50, 150.0)));
+        assert!(!tracker.is_met(&progress(100, 149.9)));
+        assert!(tracker.is_met(&progress(150, 149.9)));
+
+        let mut tracker = StopConditionTracker::new(&condition);
+        assert!(!tracker.is_met(&progress(0, 0.0)));
+        assert!(tracker.is_met(&progress(100, 0.0)));
+    }
+}
@@ -0,0 +1,263 @@
+use crate::Location;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// How duplicated (and unnamed) locations are handled by `validate_locations`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DuplicatePolicy {
+    /// Report duplicates and empty names as errors.
+    #[default]
+    Reject,
+    /// Keep the first of the duplicated locations and drop the rest; name unnamed locations.
+    Merge,
+    /// Give duplicated and unnamed locations a unique name; keep duplicated points.
+    Rename,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LocationIssue {
+    EmptyName,
+    NonFiniteCoordinates,
+    DuplicateName,
+    DuplicatePoint,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LocationDiagnostic {
+    /// Index of the location in the validated input.
+    pub index: usize,
+    pub issue: LocationIssue,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for LocationDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "location {}: {}", self.index + 1, self.message)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ValidatedLocations {
+    /// Input locations after applying the `DuplicatePolicy`, without the ones with errors.
+    pub locations: Vec<Location>,
+    /// Index in the input of each of the validated locations.
+    pub input_indices: Vec<usize>,
+    pub diagnostics: Vec<LocationDiagnostic>,
+}
+
+impl ValidatedLocations {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    pub fn errors(&self) -> Vec<LocationDiagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .cloned()
+            .collect()
+    }
+}
+
+// Bit patterns of the coordinates, with negative zero normalized, so points can be hashed
+fn point_key(location: &Location) -> (u64, u64) {
+    ((location.x + 0.0).to_bits(), (location.y + 0.0).to_bits())
+}
+
+fn unique_name(base: &str, used_names: &HashSet<String>) -> String {
+    (2..)
+        .map(|n| format!("{} ({})", base, n))
+        .find(|name| !used_names.contains(name))
+        .unwrap()
+}
+
+/// Checks the locations for empty names, non-finite coordinates, duplicated names and duplicated
+/// points, fixing what the `DuplicatePolicy` allows and reporting everything as diagnostics.
+pub fn validate_locations(locations: &[Location], policy: DuplicatePolicy) -> ValidatedLocations {
+    let mut validated = Vec::<Location>::with_capacity(locations.len());
+    let mut input_indices = Vec::<usize>::with_capacity(locations.len());
+    let mut diagnostics = Vec::<LocationDiagnostic>::new();
+    let mut names = HashMap::<String, usize>::new();
+    let mut points = HashMap::<(u64, u64), usize>::new();
+    let mut used_names: HashSet<String> = locations.iter().map(|l| l.name.clone()).collect();
+
+    let fixed_severity = if policy == DuplicatePolicy::Reject {
+        Severity::Error
+    } else {
+        Severity::Warning
+    };
+
+    for (index, location) in locations.iter().enumerate() {
+        let mut diagnostic = |issue, severity, message: String| {
+            diagnostics.push(LocationDiagnostic {
+                index,
+                issue,
+                severity,
+                message,
+            })
+        };
+
+        if !location.x.is_finite() || !location.y.is_finite() {
+            diagnostic(
+                LocationIssue::NonFiniteCoordinates,
+                Severity::Error,
+                format!(
+                    "\"{}\" has non-finite coordinates ({}, {})",
+                    location.name, location.x, location.y
+                ),
+            );
+            continue;
+        }
+
+        let mut location = location.clone();
+
+        if location.name.trim().is_empty() {
+            if policy == DuplicatePolicy::Reject {
+                diagnostic(
+                    LocationIssue::EmptyName,
+                    Severity::Error,
+                    "has an empty name".to_owned(),
+                );
+                continue;
+            }
+            location.name = format!("#{}", index + 1);
+            if used_names.contains(&location.name) {
+                location.name = unique_name(&location.name, &used_names);
+            }
+            used_names.insert(location.name.clone());
+            diagnostic(
+                LocationIssue::EmptyName,
+                Severity::Warning,
+                format!("has an empty name, renamed to \"{}\"", location.name),
+            );
+        }
+
+        if let Some(&first) = names.get(&location.name) {
+            match policy {
+                DuplicatePolicy::Reject | DuplicatePolicy::Merge => {
+                    let action = if policy == DuplicatePolicy::Merge {
+                        ", merged"
+                    } else {
+                        ""
+                    };
+                    diagnostic(
+                        LocationIssue::DuplicateName,
+                        fixed_severity,
+                        format!(
+                            "\"{}\" has the same name as location {}{}",
+                            location.name,
+                            first + 1,
+                            action
+                        ),
+                    );
+                    continue;
+                }
+                DuplicatePolicy::Rename => {
+                    let name = unique_name(&location.name, &used_names);
+                    diagnostic(
+                        LocationIssue::DuplicateName,
+                        Severity::Warning,
+                        format!(
+                            "\"{}\" has the same name as location {}, renamed to \"{}\"",
+                            location.name,
+                            first + 1,
+                            name
+                        ),
+                    );
+                    used_names.insert(name.clone());
+                    location.name = name;
+                }
+            }
+        } else if let Some(&first) = points.get(&point_key(&location)) {
+            let action = match policy {
+                DuplicatePolicy::Reject => "",
+                DuplicatePolicy::Merge => ", merged",
+                DuplicatePolicy::Rename => ", kept",
+            };
+            diagnostic(
+                LocationIssue::DuplicatePoint,
+                fixed_severity,
+                format!(
+                    "\"{}\" is at the same point as location {}{}",
+                    location.name,
+                    first + 1,
+                    action
+                ),
+            );
+            if policy != DuplicatePolicy::Rename {
+                continue;
+            }
+        }
+
+        names.entry(location.name.clone()).or_insert(index);
+        points.entry(point_key(&location)).or_insert(index);
+        validated.push(location);
+        input_indices.push(index);
+    }
+
+    ValidatedLocations {
+        locations: validated,
+        input_indices,
+        diagnostics,
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(name: &str, x: f64, y: f64) -> Location {
+        Location {
+            name: name.to_owned(),
+            x,
+            y,
+        }
+    }
+
+    #[test]
+    fn test_validate_locations_policies() {
+        let locations = vec![
+            location("A", 0.0, 0.0),
+            location("A", 10.0, 0.0),
+            location("B", 0.0, 0.0),
+            location("", 5.0, 5.0),
+            location("C", f64::NAN, 1.0),
+        ];
+
+        let rejected = validate_locations(&locations, DuplicatePolicy::Reject);
+        assert_eq!(rejected.errors().len(), 4);
+        assert_eq!(rejected.locations, vec![location("A", 0.0, 0.0)]);
+
+        let merged = validate_locations(&locations, DuplicatePolicy::Merge);
+        assert_eq!(merged.errors().len(), 1);
+        assert_eq!(
+            merged.locations,
+            vec![location("A", 0.0, 0.0), location("#4", 5.0, 5.0)]
+        );
+        assert_eq!(merged.input_indices, vec![0, 3]);
+
+        let renamed = validate_locations(&locations, DuplicatePolicy::Rename);
+        assert_eq!(renamed.errors().len(), 1);
+        assert_eq!(
+            renamed.locations,
+            vec![
+                location("A", 0.0, 0.0),
+                location("A (2)", 10.0, 0.0),
+                location("B", 0.0, 0.0),
+                location("#4", 5.0, 5.0),
+            ]
+        );
+        assert_eq!(renamed.input_indices, vec![0, 1, 2, 3]);
+    }
+}
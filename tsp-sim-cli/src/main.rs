@@ -0,0 +1,392 @@
+// Headless solver: reads the locations from a file, runs a simulation reporting its progress on
+// stderr, and writes the best route found.
+//
+// exit codes: 0 solved, 1 failed, 2 invalid input or arguments, 3 time limit reached
+
+use clap::{Parser, ValueEnum};
+use std::cell::Cell;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use tsp_sim_agent::{
+    read_locations, write_route, Format, GeneticSimulation, ImageFormat, Location, RenderOptions,
+    Route, Simulation, SimulationControl, SimulationError, SimulationEvent, StopCondition,
+};
+use tsp_sim_agent_parallel::{DistributedSimulation, IslandModel, ParallelSimulation};
+
+const EXIT_FAILURE: u8 = 1;
+const EXIT_INVALID_INPUT: u8 = 2;
+const EXIT_TIME_LIMIT: u8 = 3;
+
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "Searches for the shortest route that visits all the locations"
+)]
+struct Arguments {
+    /// Locations file, or - to read them from stdin.
+    input: PathBuf,
+
+    /// Format of the locations; detected from the file extension by default.
+    #[arg(long, value_enum)]
+    input_format: Option<FileFormat>,
+
+    /// File the best route is written to, instead of stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Format of the route; detected from the output file extension, or RON, by default. TSPLIB
+    /// routes are tours, usually written to .tour files.
+    #[arg(long, value_enum)]
+    output_format: Option<FileFormat>,
+
+    /// Also draws the best route to an SVG or PNG image.
+    #[arg(long)]
+    image: Option<PathBuf>,
+
+    /// Title of the image.
+    #[arg(long, requires = "image")]
+    title: Option<String>,
+
+    #[arg(short, long, value_enum, default_value_t = Algorithm::Parallel)]
+    algorithm: Algorithm,
+
+    #[arg(long, default_value_t = 200)]
+    population: usize,
+
+    #[arg(long)]
+    max_iterations: Option<usize>,
+
+    /// Stops when the champion has not changed for this many iterations.
+    #[arg(long)]
+    assume_convergence: Option<usize>,
+
+    /// Threads of the parallel algorithm; one per core by default.
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Iterations between migrations of routes between the threads of the parallel algorithm.
+    #[arg(long)]
+    migration_interval: Option<usize>,
+
+    /// Seeds the simulation, so that runs can be reproduced.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Stops after this many seconds, exiting with code 3.
+    #[arg(long)]
+    time_limit: Option<f64>,
+
+    /// Stops as soon as a route this short is found.
+    #[arg(long)]
+    target_distance: Option<f64>,
+
+    /// Coordinates tsp-sim-worker processes that connect to this address instead of solving here;
+    /// the workers choose their own simulation settings.
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        conflicts_with_all = ["algorithm", "population", "max_iterations", "assume_convergence", "threads"]
+    )]
+    distributed: Option<String>,
+
+    /// Workers the distributed coordinator waits for before starting.
+    #[arg(long, requires = "distributed")]
+    workers: Option<usize>,
+
+    /// Does not report the progress on stderr.
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug)]
+enum FileFormat {
+    Ron,
+    Json,
+    Csv,
+    Geojson,
+    Tsplib,
+}
+
+impl From<FileFormat> for Format {
+    fn from(file_format: FileFormat) -> Self {
+        match file_format {
+            FileFormat::Ron => Format::Ron,
+            FileFormat::Json => Format::Json,
+            FileFormat::Csv => Format::Csv,
+            FileFormat::Geojson => Format::GeoJson,
+            FileFormat::Tsplib => Format::Tsplib,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum, Debug)]
+enum Algorithm {
+    /// A single genetic simulation.
+    Genetic,
+    /// A genetic simulation on every thread.
+    Parallel,
+}
+
+// A failure, and the exit code it is reported with
+struct Failure(u8, String);
+
+impl From<SimulationError> for Failure {
+    fn from(error: SimulationError) -> Self {
+        let exit_code = match error {
+            SimulationError::InvalidInput(_)
+            | SimulationError::InvalidLocations(_)
+            | SimulationError::PopulationTooSmall { .. }
+            | SimulationError::InvalidIterationSettings { .. }
+            | SimulationError::InvalidStopCondition(_)
+            | SimulationError::InvalidIslandModel(_)
+            | SimulationError::NoThreads => EXIT_INVALID_INPUT,
+            _ => EXIT_FAILURE,
+        };
+        Failure(exit_code, error.to_string())
+    }
+}
+
+fn main() -> ExitCode {
+    let arguments = Arguments::parse();
+    match solve(&arguments) {
+        Ok(exit_code) => exit_code,
+        Err(Failure(exit_code, message)) => {
+            eprintln!("error: {}", message);
+            ExitCode::from(exit_code)
+        }
+    }
+}
+
+fn solve(arguments: &Arguments) -> Result<ExitCode, Failure> {
+    let locations = read_input(arguments)?;
+    let image_format = image_format(arguments)?;
+
+    let (route, met_condition) = match (&arguments.distributed, arguments.algorithm) {
+        (Some(address), _) => run(
+            &distributed_simulation(arguments, address, locations)?,
+            arguments.quiet,
+        )?,
+        (None, Algorithm::Genetic) => {
+            run(&genetic_simulation(arguments, locations)?, arguments.quiet)?
+        }
+        (None, Algorithm::Parallel) => {
+            run(&parallel_simulation(arguments, locations)?, arguments.quiet)?
+        }
+    };
+
+    write_output(arguments, &route)?;
+    if let (Some(path), Some(format)) = (&arguments.image, image_format) {
+        write_image(path, format, arguments.title.clone(), &route)?;
+    }
+
+    if matches!(met_condition, Some(StopCondition::TimeLimit(_))) {
+        eprintln!("time limit reached, distance {:.3}", route.distance);
+        Ok(ExitCode::from(EXIT_TIME_LIMIT))
+    } else {
+        if !arguments.quiet {
+            eprintln!("solved, distance {:.3}", route.distance);
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+fn read_input(arguments: &Arguments) -> Result<Vec<Location>, Failure> {
+    let stdin = arguments.input == Path::new("-");
+    let format = match arguments.input_format {
+        Some(file_format) => Format::from(file_format),
+        None if stdin => Format::Ron,
+        None => Format::from_path(&arguments.input).ok_or_else(|| {
+            Failure(
+                EXIT_INVALID_INPUT,
+                format!(
+                    "unknown format of {}, use --input-format",
+                    arguments.input.display()
+                ),
+            )
+        })?,
+    };
+
+    let text = if stdin {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map(|_| text)
+            .map_err(|error| Failure(EXIT_FAILURE, format!("could not read stdin: {}", error)))
+    } else {
+        fs::read_to_string(&arguments.input).map_err(|error| {
+            Failure(
+                EXIT_INVALID_INPUT,
+                format!("could not read {}: {}", arguments.input.display(), error),
+            )
+        })
+    }?;
+    Ok(read_locations(&text, format)?)
+}
+
+fn write_output(arguments: &Arguments, route: &Route) -> Result<(), Failure> {
+    let format = arguments
+        .output_format
+        .map(Format::from)
+        .or_else(|| arguments.output.as_ref().and_then(Format::from_path))
+        .unwrap_or(Format::Ron);
+    let mut text = write_route(route, format)?;
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+
+    let result = match &arguments.output {
+        Some(output) => fs::write(output, text),
+        None => io::stdout().write_all(text.as_bytes()),
+    };
+    result.map_err(|error| {
+        Failure(
+            EXIT_FAILURE,
+            format!("could not write the route: {}", error),
+        )
+    })
+}
+
+// Format of the image, checked before solving so that a wrong extension fails right away
+fn image_format(arguments: &Arguments) -> Result<Option<ImageFormat>, Failure> {
+    arguments
+        .image
+        .as_ref()
+        .map(|path| {
+            ImageFormat::from_path(path).ok_or_else(|| {
+                Failure(
+                    EXIT_INVALID_INPUT,
+                    format!(
+                        "unknown image format of {}, use .svg or .png",
+                        path.display()
+                    ),
+                )
+            })
+        })
+        .transpose()
+}
+
+fn write_image(
+    path: &Path,
+    format: ImageFormat,
+    title: Option<String>,
+    route: &Route,
+) -> Result<(), Failure> {
+    let options = RenderOptions {
+        title,
+        ..RenderOptions::default()
+    };
+    fs::write(path, format.render(route, &options)?).map_err(|error| {
+        Failure(
+            EXIT_FAILURE,
+            format!("could not write {}: {}", path.display(), error),
+        )
+    })
+}
+
+fn time_limit(arguments: &Arguments) -> Result<Option<Duration>, SimulationError> {
+    arguments
+        .time_limit
+        .map(|seconds| {
+            Duration::try_from_secs_f64(seconds).map_err(|_| {
+                SimulationError::InvalidStopCondition(
+                    "time limit must be a non-negative number of seconds".to_owned(),
+                )
+            })
+        })
+        .transpose()
+}
+
+fn stop_condition(arguments: &Arguments) -> Result<Option<StopCondition>, SimulationError> {
+    let mut conditions = vec![];
+    if let Some(time_limit) = time_limit(arguments)? {
+        conditions.push(StopCondition::TimeLimit(time_limit));
+    }
+    if let Some(target_distance) = arguments.target_distance {
+        conditions.push(StopCondition::TargetDistance(target_distance));
+    }
+    Ok(match conditions.len() {
+        0 => None,
+        1 => conditions.pop(),
+        _ => Some(StopCondition::Any(conditions)),
+    })
+}
+
+fn genetic_simulation(
+    arguments: &Arguments,
+    locations: Vec<Location>,
+) -> Result<GeneticSimulation, SimulationError> {
+    let defaults = GeneticSimulation::new(vec![]);
+    GeneticSimulation::builder(locations)
+        .population_size(arguments.population)
+        .max_iterations(arguments.max_iterations.or(defaults.max_iterations))
+        .assume_convergence(arguments.assume_convergence.or(defaults.assume_convergence))
+        .stop_condition(stop_condition(arguments)?)
+        .seed(arguments.seed)
+        .build()
+}
+
+fn parallel_simulation(
+    arguments: &Arguments,
+    locations: Vec<Location>,
+) -> Result<ParallelSimulation, SimulationError> {
+    let defaults = ParallelSimulation::new(vec![]);
+    let island_model = arguments.migration_interval.map(|interval| IslandModel {
+        interval,
+        ..IslandModel::default()
+    });
+    ParallelSimulation::builder(locations)
+        .population_size(arguments.population)
+        .max_iterations(arguments.max_iterations.or(defaults.max_iterations))
+        .assume_convergence(arguments.assume_convergence.or(defaults.assume_convergence))
+        .stop_condition(stop_condition(arguments)?)
+        .threads(arguments.threads.unwrap_or(defaults.threads))
+        .island_model(island_model)
+        .seed(arguments.seed)
+        .build()
+}
+
+fn distributed_simulation(
+    arguments: &Arguments,
+    address: &str,
+    locations: Vec<Location>,
+) -> Result<DistributedSimulation, SimulationError> {
+    let workers = arguments.workers.unwrap_or(1);
+    let mut simulation = DistributedSimulation::bind(address, locations, workers)?;
+    simulation.island_model = arguments.migration_interval.map(|interval| IslandModel {
+        interval,
+        ..IslandModel::default()
+    });
+    simulation.stop_condition = stop_condition(arguments)?;
+    simulation.seed = arguments.seed;
+    simulation.validate()?;
+    if !arguments.quiet {
+        eprintln!(
+            "waiting for {} workers on {}",
+            workers,
+            simulation.local_addr()?
+        );
+    }
+    Ok(simulation)
+}
+
+// Runs the simulation, returning its route and the stop condition that ended it, if any
+fn run<S: Simulation>(
+    simulation: &S,
+    quiet: bool,
+) -> Result<(Route, Option<StopCondition>), SimulationError> {
+    let met_condition = Cell::new(None);
+    let route = simulation.run(&SimulationControl::new(), |event| match event {
+        SimulationEvent::NewChampion(route, iteration) if !quiet => {
+            eprintln!("iteration {}: distance {:.3}", iteration, route.distance)
+        }
+        SimulationEvent::WorkerFailed(worker, message) => {
+            eprintln!("thread {} failed: {}", worker, message)
+        }
+        SimulationEvent::StopConditionMet(condition) => met_condition.set(Some(condition)),
+        _ => {}
+    })?;
+    Ok((route, met_condition.into_inner()))
+}
@@ -0,0 +1,170 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use tsp_sim_agent::{read_locations, write_locations, Format, InstanceGenerator, Location, Shape};
+
+// Quick settings, so that the small instances are solved in a moment
+const QUICK: [&str; 6] = [
+    "--algorithm",
+    "genetic",
+    "--max-iterations",
+    "300",
+    "--assume-convergence",
+    "100",
+];
+
+fn tsp_sim_cli(arguments: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_tsp-sim-cli"));
+    command.args(arguments).arg("--quiet");
+    command
+}
+
+fn instance(size: usize) -> Vec<Location> {
+    InstanceGenerator::new(Shape::Uniform, size, 3)
+        .generate()
+        .unwrap()
+}
+
+// Writes the file in the temporary directory of the tests, returning its path
+fn write_file(name: &str, contents: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+// The route written to stdout as CSV, as the locations it visits
+fn route_locations(output: &Output) -> Vec<Location> {
+    read_locations(&String::from_utf8_lossy(&output.stdout), Format::Csv).unwrap()
+}
+
+#[test]
+fn test_solve_instance() {
+    let locations = instance(10);
+    let path = write_file(
+        "solve.ron",
+        &write_locations(&locations, Format::Ron).unwrap(),
+    );
+
+    let output = tsp_sim_cli(&QUICK)
+        .arg(&path)
+        .args(["--output-format", "csv"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let route = route_locations(&output);
+    assert_eq!(route.len(), locations.len());
+    assert!(locations.iter().all(|location| route.contains(location)));
+}
+
+#[test]
+fn test_detect_formats() {
+    let locations = instance(10);
+    for format in [Format::Json, Format::Csv, Format::GeoJson, Format::Tsplib] {
+        let path = write_file(
+            &format!("detect.{}", format.extension()),
+            &write_locations(&locations, format).unwrap(),
+        );
+        let route_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+            .join(format!("detect-{}-route.csv", format.extension()));
+
+        let output = tsp_sim_cli(&QUICK)
+            .arg(&path)
+            .arg("--output")
+            .arg(&route_path)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0), "{:?}", format);
+        let route = read_locations(&fs::read_to_string(&route_path).unwrap(), Format::Csv);
+        assert_eq!(route.map(|route| route.len()), Ok(10), "{:?}", format);
+    }
+
+    let unknown = write_file("detect.txt", "");
+    let output = tsp_sim_cli(&QUICK).arg(&unknown).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_read_stdin() {
+    let locations = instance(10);
+    for (format, format_arguments) in [
+        (Format::Ron, vec![]),
+        (Format::Json, vec!["--input-format", "json"]),
+    ] {
+        let mut child = tsp_sim_cli(&QUICK)
+            .arg("-")
+            .args(format_arguments)
+            .args(["--output-format", "csv"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(write_locations(&locations, format).unwrap().as_bytes())
+            .unwrap();
+
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.code(), Some(0), "{:?}", format);
+        assert_eq!(route_locations(&output).len(), 10, "{:?}", format);
+    }
+}
+
+#[test]
+fn test_invalid_input() {
+    let malformed = write_file("malformed.ron", "[(name: \"1\", x: 0.0");
+    let output = tsp_sim_cli(&QUICK).arg(&malformed).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error:"));
+
+    let path = write_file(
+        "population.ron",
+        &write_locations(&instance(10), Format::Ron).unwrap(),
+    );
+    let output = tsp_sim_cli(&QUICK)
+        .arg(&path)
+        .args(["--population", "3"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_time_limit() {
+    let path = write_file(
+        "time-limit.csv",
+        &write_locations(&instance(2000), Format::Csv).unwrap(),
+    );
+
+    let output = tsp_sim_cli(&["--time-limit", "0.01", "--output-format", "csv"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    // the best route found so far is still written
+    assert_eq!(route_locations(&output).len(), 2000);
+}
+
+#[test]
+fn test_target_distance_before_time_limit() {
+    let path = write_file(
+        "target-distance.csv",
+        &write_locations(&instance(10), Format::Csv).unwrap(),
+    );
+
+    let output = tsp_sim_cli(&[
+        "--time-limit",
+        "60",
+        "--target-distance",
+        "1000000",
+        "--output-format",
+        "csv",
+    ])
+    .arg(&path)
+    .output()
+    .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(route_locations(&output).len(), 10);
+}
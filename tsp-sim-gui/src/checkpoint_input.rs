@@ -0,0 +1,110 @@
+use eframe::egui;
+use eframe::egui::DragValue;
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use tsp_sim_agent::{Checkpoint, SimulationError};
+
+// A checkpoint to write, with the context to repaint if writing it fails
+type SaveRequest = (String, Checkpoint, egui::Context);
+
+// Side panel inputs to seed the simulation, save its checkpoints and resume from them
+pub struct CheckpointInput {
+    seed_enabled: bool,
+    seed: u64,
+    path: String,
+    save_enabled: bool,
+    interval: usize,
+    resume: bool,
+    // checkpoints are written on another thread, which sends back the errors
+    save_sender: Sender<SaveRequest>,
+    save_error_receiver: Receiver<SimulationError>,
+}
+
+impl CheckpointInput {
+    pub fn new() -> Self {
+        let (save_sender, save_receiver) = mpsc::channel();
+        let (save_error_sender, save_error_receiver) = mpsc::channel();
+        thread::spawn(move || write_checkpoints(save_receiver, save_error_sender));
+        Self {
+            seed_enabled: false,
+            seed: 0,
+            path: "checkpoint.ron".to_string(),
+            save_enabled: false,
+            interval: 10_000,
+            resume: false,
+            save_sender,
+            save_error_receiver,
+        }
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed_enabled.then_some(self.seed)
+    }
+
+    pub fn checkpoint_interval(&self) -> Option<usize> {
+        self.save_enabled.then_some(self.interval)
+    }
+
+    // The checkpoint to resume from, read from the checkpoint file
+    pub fn checkpoint(&self) -> Result<Option<Checkpoint>, SimulationError> {
+        if self.resume {
+            Checkpoint::load(&self.path).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Writes the checkpoint in the background, so that large populations don't stall the UI
+    pub fn save(&self, checkpoint: Checkpoint, egui_ctx: &egui::Context) {
+        if self.save_enabled {
+            let _ = self
+                .save_sender
+                .send((self.path.clone(), checkpoint, egui_ctx.clone()));
+        }
+    }
+
+    // An error of a checkpoint written in the background, if any
+    pub fn save_error(&self) -> Option<SimulationError> {
+        self.save_error_receiver.try_recv().ok()
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.seed_enabled, "Seed");
+            ui.add_enabled(self.seed_enabled, DragValue::new(&mut self.seed))
+                .on_hover_text("Seeded runs with the same settings find the same routes");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Checkpoint");
+            ui.text_edit_singleline(&mut self.path);
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.save_enabled, "Save every");
+            ui.add_enabled(
+                self.save_enabled,
+                DragValue::new(&mut self.interval)
+                    .clamp_range(1..=usize::MAX)
+                    .suffix(" iterations"),
+            );
+        });
+        ui.checkbox(&mut self.resume, "Resume from checkpoint");
+    }
+}
+
+// Only the latest of the pending checkpoints is written, the others being already outdated
+fn write_checkpoints(
+    save_receiver: Receiver<SaveRequest>,
+    save_error_sender: Sender<SimulationError>,
+) {
+    while let Ok(request) = save_receiver.recv() {
+        let (path, checkpoint, egui_ctx) = save_receiver.try_iter().last().unwrap_or(request);
+        if let Err(error) = checkpoint.save(&path) {
+            if save_error_sender.send(error).is_err() {
+                return;
+            }
+            egui_ctx.request_repaint();
+        }
+    }
+}
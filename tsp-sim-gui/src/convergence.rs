@@ -0,0 +1,164 @@
+use eframe::egui;
+use eframe::egui::plot::{Legend, Line, Plot, PlotPoints};
+use eframe::epaint::Color32;
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+const MAX_PAST_RUNS: usize = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum XAxis {
+    Iterations,
+    Seconds,
+}
+
+struct Sample {
+    iteration: usize,
+    seconds: f64,
+    distance: f64,
+}
+
+impl Sample {
+    fn point(&self, x_axis: XAxis) -> [f64; 2] {
+        match x_axis {
+            XAxis::Iterations => [self.iteration as f64, self.distance],
+            XAxis::Seconds => [self.seconds, self.distance],
+        }
+    }
+}
+
+// Champion and mean population distances recorded during a single simulation run
+struct Run {
+    number: usize,
+    start_time: Instant,
+    champion: Vec<Sample>,
+    mean: Vec<Sample>,
+}
+
+impl Run {
+    fn new(number: usize) -> Self {
+        Self {
+            number,
+            start_time: Instant::now(),
+            champion: vec![],
+            mean: vec![],
+        }
+    }
+
+    fn sample(&self, distance: f64, iteration: usize) -> Sample {
+        Sample {
+            iteration,
+            seconds: self.start_time.elapsed().as_secs_f64(),
+            distance,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.champion.is_empty() && self.mean.is_empty()
+    }
+}
+
+fn line(samples: &[Sample], x_axis: XAxis) -> Line {
+    Line::new(PlotPoints::new(
+        samples.iter().map(|sample| sample.point(x_axis)).collect(),
+    ))
+}
+
+// Plots the champion distance (and mean population distance) of the current and past runs
+pub struct ConvergencePlot {
+    current: Option<Run>,
+    past: VecDeque<Run>,
+    runs_started: usize,
+    x_axis: XAxis,
+    overlay_past_runs: bool,
+}
+
+impl ConvergencePlot {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            past: VecDeque::with_capacity(MAX_PAST_RUNS + 1),
+            runs_started: 0,
+            x_axis: XAxis::Iterations,
+            overlay_past_runs: true,
+        }
+    }
+
+    pub fn start_run(&mut self) {
+        if let Some(run) = self.current.take() {
+            if !run.is_empty() {
+                self.past.push_back(run);
+                if self.past.len() > MAX_PAST_RUNS {
+                    self.past.pop_front();
+                }
+            }
+        }
+        self.runs_started += 1;
+        self.current = Some(Run::new(self.runs_started));
+    }
+
+    pub fn record_champion(&mut self, distance: f64, iteration: usize) {
+        if let Some(run) = &mut self.current {
+            let sample = run.sample(distance, iteration);
+            run.champion.push(sample);
+        }
+    }
+
+    pub fn record_mean(&mut self, distance: f64, iteration: usize) {
+        if let Some(run) = &mut self.current {
+            let sample = run.sample(distance, iteration);
+            run.mean.push(sample);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.current = None;
+        self.past.clear();
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("X axis:");
+            ui.radio_value(&mut self.x_axis, XAxis::Iterations, "Iterations");
+            ui.radio_value(&mut self.x_axis, XAxis::Seconds, "Time (s)");
+            ui.separator();
+            ui.checkbox(&mut self.overlay_past_runs, "Overlay past runs");
+            if ui.small_button("Clear").clicked() {
+                self.clear();
+            }
+        });
+
+        let x_axis = self.x_axis;
+        let past_runs = if self.overlay_past_runs {
+            self.past.iter().collect()
+        } else {
+            vec![]
+        };
+
+        Plot::new("convergence_plot")
+            .legend(Legend::default())
+            .include_y(0.0)
+            .show(ui, |plot_ui| {
+                for run in past_runs {
+                    plot_ui.line(
+                        line(&run.champion, x_axis)
+                            .color(Color32::GRAY)
+                            .name(format!("Run {} champion", run.number)),
+                    );
+                }
+                if let Some(run) = &self.current {
+                    plot_ui.line(
+                        line(&run.mean, x_axis)
+                            .color(Color32::LIGHT_BLUE)
+                            .name(format!("Run {} mean", run.number)),
+                    );
+                    plot_ui.line(
+                        line(&run.champion, x_axis)
+                            .color(Color32::LIGHT_RED)
+                            .name(format!("Run {} champion", run.number)),
+                    );
+                }
+            });
+    }
+}
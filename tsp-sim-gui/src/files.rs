@@ -0,0 +1,253 @@
+use eframe::egui;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tsp_sim_agent::{
+    read_locations, write_locations, write_route, Format, ImageFormat, Location, RenderOptions,
+    Route, SimulationError,
+};
+
+const MAX_RECENT_FILES: usize = 8;
+
+// Storage key of the recent files, persisted between sessions
+pub const RECENT_FILES_KEY: &str = "recent_files";
+
+pub enum FileAction {
+    Open(PathBuf),
+    Save(PathBuf),
+    ExportRoute(PathBuf),
+    ExportImage(PathBuf),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FileDialog {
+    Open,
+    SaveAs,
+    ExportRoute,
+    ExportImage,
+}
+
+// File menu, path dialog and recent files of the locations input
+pub struct Files {
+    pub current: Option<PathBuf>,
+    recent: Vec<PathBuf>,
+    dialog: Option<FileDialog>,
+    dialog_path: String,
+}
+
+impl Files {
+    pub fn new(recent: Vec<PathBuf>) -> Self {
+        Self {
+            current: None,
+            recent,
+            dialog: None,
+            dialog_path: String::new(),
+        }
+    }
+
+    pub fn recent(&self) -> &[PathBuf] {
+        &self.recent
+    }
+
+    // Makes the file the current one, first in the recent files
+    pub fn set_current(&mut self, path: &Path) {
+        self.current = Some(path.to_owned());
+        self.recent.retain(|recent| recent != path);
+        self.recent.insert(0, path.to_owned());
+        self.recent.truncate(MAX_RECENT_FILES);
+    }
+
+    pub fn menu_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        simulation_running: bool,
+        route_available: bool,
+    ) -> Option<FileAction> {
+        let mut action = None;
+        ui.add_enabled_ui(!simulation_running, |ui| {
+            if ui.button("Open...").clicked() {
+                self.open_dialog(FileDialog::Open);
+                ui.close_menu();
+            }
+            ui.add_enabled_ui(!self.recent.is_empty(), |ui| {
+                ui.menu_button("Open Recent", |ui| {
+                    for path in &self.recent {
+                        if ui.button(path.display().to_string()).clicked() {
+                            action = Some(FileAction::Open(path.clone()));
+                            ui.close_menu();
+                        }
+                    }
+                });
+            });
+        });
+        ui.separator();
+        if ui.button("Save").clicked() {
+            match &self.current {
+                Some(path) => action = Some(FileAction::Save(path.clone())),
+                None => self.open_dialog(FileDialog::SaveAs),
+            }
+            ui.close_menu();
+        }
+        if ui.button("Save As...").clicked() {
+            self.open_dialog(FileDialog::SaveAs);
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui
+            .add_enabled(route_available, egui::Button::new("Export Route..."))
+            .clicked()
+        {
+            self.open_dialog(FileDialog::ExportRoute);
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(route_available, egui::Button::new("Export Image..."))
+            .clicked()
+        {
+            self.open_dialog(FileDialog::ExportImage);
+            ui.close_menu();
+        }
+        action
+    }
+
+    fn open_dialog(&mut self, dialog: FileDialog) {
+        self.dialog = Some(dialog);
+        self.dialog_path = match (dialog, &self.current) {
+            (FileDialog::ExportRoute, Some(path)) => {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                path.with_file_name(format!("{}-route.csv", stem))
+                    .display()
+                    .to_string()
+            }
+            (FileDialog::ExportRoute, None) => "route.csv".to_string(),
+            (FileDialog::ExportImage, Some(path)) => {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                path.with_file_name(format!("{}-route.png", stem))
+                    .display()
+                    .to_string()
+            }
+            (FileDialog::ExportImage, None) => "route.png".to_string(),
+            (_, Some(path)) => path.display().to_string(),
+            (_, None) => String::new(),
+        };
+    }
+
+    // Asks for the path of the file to open or write to
+    pub fn dialog_ui(&mut self, ctx: &egui::Context) -> Option<FileAction> {
+        let dialog = self.dialog?;
+        let title = match dialog {
+            FileDialog::Open => "Open",
+            FileDialog::SaveAs => "Save As",
+            FileDialog::ExportRoute => "Export Route",
+            FileDialog::ExportImage => "Export Image",
+        };
+
+        let mut action = None;
+        let mut open = true;
+        egui::Window::new(title)
+            .collapsible(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("File");
+                    ui.text_edit_singleline(&mut self.dialog_path);
+                });
+                let path = PathBuf::from(self.dialog_path.trim());
+                if dialog == FileDialog::ExportImage {
+                    ui.label(format!("Format: {:?}", image_format(&path)))
+                        .on_hover_text("Detected from the extension: svg or png");
+                } else {
+                    let extensions: Vec<&str> = Format::ALL
+                        .iter()
+                        .map(|format| match dialog {
+                            FileDialog::ExportRoute => format.route_extension(),
+                            _ => format.extension(),
+                        })
+                        .collect();
+                    ui.label(format!("Format: {}", file_format(&path)))
+                        .on_hover_text(format!(
+                            "Detected from the extension: {}",
+                            extensions.join(", ")
+                        ));
+                }
+
+                if dialog == FileDialog::Open && !self.recent.is_empty() {
+                    ui.separator();
+                    ui.label("Recent files");
+                    for recent in &self.recent {
+                        if ui
+                            .selectable_label(false, recent.display().to_string())
+                            .clicked()
+                        {
+                            self.dialog_path = recent.display().to_string();
+                        }
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let confirm_text = match dialog {
+                        FileDialog::Open => "Open",
+                        FileDialog::SaveAs => "Save",
+                        FileDialog::ExportRoute | FileDialog::ExportImage => "Export",
+                    };
+                    let path_given = !self.dialog_path.trim().is_empty();
+                    if ui
+                        .add_enabled(path_given, egui::Button::new(confirm_text))
+                        .clicked()
+                    {
+                        action = Some(match dialog {
+                            FileDialog::Open => FileAction::Open(path),
+                            FileDialog::SaveAs => FileAction::Save(path),
+                            FileDialog::ExportRoute => FileAction::ExportRoute(path),
+                            FileDialog::ExportImage => FileAction::ExportImage(path),
+                        });
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.dialog = None;
+                    }
+                });
+            });
+        if !open || action.is_some() {
+            self.dialog = None;
+        }
+        action
+    }
+}
+
+// Format of a file from its extension, RON by default
+pub fn file_format(path: &Path) -> Format {
+    Format::from_path(path).unwrap_or(Format::Ron)
+}
+
+// Format of an image from its extension, PNG by default
+pub fn image_format(path: &Path) -> ImageFormat {
+    ImageFormat::from_path(path).unwrap_or(ImageFormat::Png)
+}
+
+fn file_error(path: &Path, error: std::io::Error) -> SimulationError {
+    SimulationError::InvalidInput(format!("{}: {}", path.display(), error))
+}
+
+pub fn read_locations_file(path: &Path) -> Result<Vec<Location>, SimulationError> {
+    let text = fs::read_to_string(path).map_err(|error| file_error(path, error))?;
+    read_locations(&text, file_format(path))
+}
+
+pub fn write_locations_file(path: &Path, locations: &[Location]) -> Result<(), SimulationError> {
+    let text = write_locations(locations, file_format(path))?;
+    fs::write(path, text).map_err(|error| file_error(path, error))
+}
+
+pub fn write_route_file(path: &Path, route: &Route) -> Result<(), SimulationError> {
+    let text = write_route(route, file_format(path))?;
+    fs::write(path, text).map_err(|error| file_error(path, error))
+}
+
+pub fn write_image_file(
+    path: &Path,
+    route: &Route,
+    options: &RenderOptions,
+) -> Result<(), SimulationError> {
+    let image = image_format(path).render(route, options)?;
+    fs::write(path, image).map_err(|error| file_error(path, error))
+}
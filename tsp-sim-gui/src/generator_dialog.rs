@@ -0,0 +1,104 @@
+use eframe::egui;
+use eframe::egui::DragValue;
+use eframe::epaint::Color32;
+
+use tsp_sim_agent::{InstanceGenerator, Location, Shape, SimulationError};
+
+const MAX_SIZE: usize = 100_000;
+
+// Window generating a random, grid or circle instance to replace the locations input
+pub struct GeneratorDialog {
+    open: bool,
+    generator: InstanceGenerator,
+    error: Option<SimulationError>,
+}
+
+impl GeneratorDialog {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            generator: InstanceGenerator::new(Shape::Uniform, 100, 0),
+            error: None,
+        }
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+        self.error = None;
+    }
+
+    // The generated locations; they can't replace the input while a simulation is running
+    pub fn ui(&mut self, ctx: &egui::Context, simulation_running: bool) -> Option<Vec<Location>> {
+        let mut locations = None;
+        let mut open = self.open;
+        egui::Window::new("Generate Instance")
+            .collapsible(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let generator = &mut self.generator;
+                egui::ComboBox::from_label("Shape")
+                    .selected_text(generator.shape.to_string())
+                    .show_ui(ui, |ui| {
+                        for shape in Shape::ALL {
+                            ui.selectable_value(&mut generator.shape, shape, shape.to_string());
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    ui.label("Locations");
+                    ui.add(DragValue::new(&mut generator.size).clamp_range(1..=MAX_SIZE));
+                });
+                ui.add_enabled_ui(generator.shape.is_random(), |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Seed");
+                        ui.add(DragValue::new(&mut generator.seed));
+                        if ui.small_button("Random").clicked() {
+                            generator.seed = rand::random();
+                        }
+                    });
+                });
+                let clustered = matches!(generator.shape, Shape::Clustered | Shape::Mixed);
+                ui.add_enabled_ui(clustered, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Clusters");
+                        ui.add(DragValue::new(&mut generator.clusters).clamp_range(1..=1000));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Spread");
+                        ui.add(
+                            DragValue::new(&mut generator.spread)
+                                .clamp_range(0.001..=1.0)
+                                .speed(0.005),
+                        )
+                        .on_hover_text("Standard deviation around the cluster centres");
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Extent");
+                    ui.add(
+                        DragValue::new(&mut generator.extent)
+                            .clamp_range(1.0..=f64::MAX)
+                            .speed(10.),
+                    )
+                    .on_hover_text("Side of the square the locations are generated in");
+                });
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(Color32::RED, error.to_string());
+                }
+
+                ui.separator();
+                if ui
+                    .add_enabled(!simulation_running, egui::Button::new("Generate"))
+                    .on_disabled_hover_text("Stop the simulation to replace its locations")
+                    .clicked()
+                {
+                    match self.generator.generate() {
+                        Ok(generated) => locations = Some(generated),
+                        Err(error) => self.error = Some(error),
+                    }
+                }
+            });
+        self.open = open && locations.is_none();
+        locations
+    }
+}
@@ -0,0 +1,83 @@
+use eframe::egui;
+use eframe::egui::text::{LayoutJob, TextFormat};
+use eframe::epaint::Color32;
+
+use tsp_sim_agent::Location;
+
+const ERROR_LINE_BACKGROUND: Color32 = Color32::from_rgb(110, 30, 30);
+
+// Position, starting at 1, and description of a RON syntax error
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+pub fn parse_locations(locations_ron: &str) -> Result<Vec<Location>, ParseError> {
+    ron::de::from_str(locations_ron).map_err(|error| ParseError {
+        line: error.position.line,
+        column: error.position.col,
+        message: error.code.to_string(),
+    })
+}
+
+// Writes the locations one per line, as in the examples
+pub fn locations_ron(locations: &[Location]) -> String {
+    let mut text = String::from("[\n");
+    for location in locations {
+        text.push_str(&format!(
+            "  (name: {}, x: {}, y: {}),\n",
+            ron::to_string(&location.name).unwrap_or_default(),
+            ron::to_string(&location.x).unwrap_or_default(),
+            ron::to_string(&location.y).unwrap_or_default(),
+        ));
+    }
+    text.push(']');
+    text
+}
+
+// Multiline editor of the locations RON, with the line of the parse error highlighted
+pub fn locations_editor_ui(
+    ui: &mut egui::Ui,
+    locations_ron: &mut String,
+    parse_error: Option<&ParseError>,
+) -> egui::Response {
+    let error_line = parse_error.map(|parse_error| parse_error.line);
+    let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+        let format = TextFormat {
+            font_id: egui::TextStyle::Body.resolve(ui.style()),
+            color: ui
+                .visuals()
+                .override_text_color
+                .unwrap_or_else(|| ui.visuals().widgets.inactive.text_color()),
+            ..TextFormat::default()
+        };
+
+        let mut job = LayoutJob::default();
+        for (index, line) in text.split_inclusive('\n').enumerate() {
+            let line_format = if error_line == Some(index + 1) {
+                TextFormat {
+                    background: ERROR_LINE_BACKGROUND,
+                    ..format.clone()
+                }
+            } else {
+                format.clone()
+            };
+            job.append(line, 0.0, line_format);
+        }
+        job.wrap.max_width = wrap_width;
+        ui.fonts().layout_job(job)
+    };
+
+    ui.add(egui::TextEdit::multiline(locations_ron).layouter(&mut layouter))
+}
+
+pub fn parse_error_ui(ui: &mut egui::Ui, parse_error: &ParseError) {
+    ui.colored_label(
+        Color32::RED,
+        format!(
+            "line {}, column {}: {}",
+            parse_error.line, parse_error.column, parse_error.message
+        ),
+    );
+}
@@ -3,36 +3,74 @@
 extern crate itertools;
 extern crate ron;
 
+mod checkpoint_input;
+mod convergence;
 mod examples;
+mod files;
+mod generator_dialog;
+mod locations_editor;
+mod portfolio_input;
+mod route_canvas;
+mod stop_condition_input;
+mod timeline;
+mod worker_table;
 
 use anyhow::Result;
-use eframe::{egui, emath::pos2, epaint::Color32, epaint::Stroke};
-use itertools::Itertools;
-
+use checkpoint_input::CheckpointInput;
+use convergence::ConvergencePlot;
+use eframe::{egui, epaint::Color32};
+use files::{FileAction, Files};
+use generator_dialog::GeneratorDialog;
+use locations_editor::ParseError;
+use portfolio_input::PortfolioInput;
+use route_canvas::{CanvasEdit, RouteCanvas};
+use stop_condition_input::StopConditionInput;
+use timeline::Timeline;
+
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{mpsc, Arc};
 use std::thread;
 use std::time::Instant;
-use tsp_sim_agent::{Location, Simulation, SimulationEvent};
-use tsp_sim_agent_parallel::ParallelSimulation;
+use tsp_sim_agent::{
+    validate_locations, write_locations, DuplicatePolicy, Format, GeneticSimulation, Location,
+    LocationDiagnostic, PortfolioStanding, RenderOptions, Route, Severity, Simulation,
+    SimulationControl, SimulationError, SimulationEvent, WorkerStatistics,
+};
+use tsp_sim_agent_parallel::{IslandModel, MigrationTopology, ParallelSimulation, Portfolio};
 
 fn main() -> Result<()> {
-    let options = eframe::NativeOptions::default();
+    let options = eframe::NativeOptions {
+        drag_and_drop_support: true,
+        ..eframe::NativeOptions::default()
+    };
     eframe::run_native(
         "TSP simulator",
         options,
         Box::new(|cc| {
             let egui_ctx = cc.egui_ctx.clone();
+            let recent_files: Vec<PathBuf> = cc
+                .storage
+                .and_then(|storage| eframe::get_value(storage, files::RECENT_FILES_KEY))
+                .unwrap_or_default();
 
             let (command_sender, command_receiver) = mpsc::channel();
             let (event_sender, event_receiver) = mpsc::channel();
+            let (error_sender, error_receiver) = mpsc::channel();
             thread::spawn(move || {
-                simulation_control_loop(command_receiver, event_sender, egui_ctx)
+                simulation_control_loop(command_receiver, event_sender, error_sender, egui_ctx)
             });
 
-            Box::new(App::new(command_sender, event_receiver))
+            Box::new(App::new(
+                command_sender,
+                event_receiver,
+                error_receiver,
+                recent_files,
+            ))
         }),
     );
     Ok(())
@@ -71,41 +109,201 @@ impl ThroughputCounter {
 pub struct App {
     locations_ron: String,
     locations: Vec<Location>,
+    // index in the input of each validated location, by its validated name
+    locations_input_indices: HashMap<String, usize>,
+    duplicate_policy: DuplicatePolicy,
+    locations_diagnostics: Vec<LocationDiagnostic>,
+    locations_parse_error: Option<ParseError>,
     route: Vec<String>,
     route_distance: f64,
     route_iteration: usize,
     simulation_running: bool,
+    simulation_paused: bool,
+    simulation_error: Option<SimulationError>,
     population_text: String,
     population: usize,
+    threads: usize,
+    max_threads: usize,
+    pin_threads: bool,
+    island_model_enabled: bool,
+    island_model: IslandModel,
+    worker_restarts: usize,
+    worker_failures: Vec<String>,
     total_iterations: usize,
+    total_evaluations: usize,
+    total_migrations: usize,
+    stop_condition_input: StopConditionInput,
+    portfolio_input: PortfolioInput,
+    checkpoint_input: CheckpointInput,
+    files: Files,
+    generator_dialog: GeneratorDialog,
+    leaderboard: Vec<PortfolioStanding>,
+    worker_statistics: Vec<WorkerStatistics>,
+    show_worker_table: bool,
     throughput_counter: ThroughputCounter,
+    convergence_plot: ConvergencePlot,
+    show_convergence_plot: bool,
+    show_route_list: bool,
+    timeline: Timeline,
+    show_timeline: bool,
+    route_canvas: RouteCanvas,
 
     // Simulation thread events and control
     command_sender: Sender<SimulationCommand>,
     event_receiver: Receiver<SimulationEvent>,
+    // errors that ended a simulation run
+    error_receiver: Receiver<SimulationError>,
 }
 
 impl App {
     fn new(
         command_sender: Sender<SimulationCommand>,
         event_receiver: Receiver<SimulationEvent>,
+        error_receiver: Receiver<SimulationError>,
+        recent_files: Vec<PathBuf>,
     ) -> Self {
         let locations: Vec<Location> = ron::de::from_str(examples::EXAMPLE1_RON).unwrap();
+        let max_threads = thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(1);
         Self {
             locations_ron: examples::EXAMPLE1_RON.to_string(),
             route: locations_names(&locations),
+            locations_input_indices: locations
+                .iter()
+                .enumerate()
+                .map(|(index, location)| (location.name.clone(), index))
+                .collect(),
             locations,
+            duplicate_policy: DuplicatePolicy::default(),
+            locations_diagnostics: vec![],
+            locations_parse_error: None,
             route_distance: f64::NAN,
             route_iteration: 0,
             simulation_running: false,
+            simulation_paused: false,
+            simulation_error: None,
             population_text: "200".to_string(),
             population: 200,
+            threads: max_threads,
+            max_threads,
+            pin_threads: false,
+            island_model_enabled: false,
+            island_model: IslandModel::default(),
+            worker_restarts: 1,
+            worker_failures: vec![],
             total_iterations: 0,
+            total_evaluations: 0,
+            total_migrations: 0,
+            stop_condition_input: StopConditionInput::new(),
+            portfolio_input: PortfolioInput::new(),
+            checkpoint_input: CheckpointInput::new(),
+            files: Files::new(recent_files),
+            generator_dialog: GeneratorDialog::new(),
+            leaderboard: vec![],
+            worker_statistics: vec![],
+            show_worker_table: false,
             throughput_counter: ThroughputCounter::new(),
+            convergence_plot: ConvergencePlot::new(),
+            show_convergence_plot: true,
+            show_route_list: false,
+            timeline: Timeline::new(),
+            show_timeline: false,
+            route_canvas: RouteCanvas::new(),
 
             command_sender,
             event_receiver,
+            error_receiver,
+        }
+    }
+
+    fn current_route(&self) -> Route {
+        Route {
+            locations: self.locations.clone(),
+            distance: self.route_distance,
+        }
+    }
+
+    fn perform_file_action(&mut self, action: FileAction) {
+        let result = match &action {
+            FileAction::Open(path) => files::read_locations_file(path)
+                .and_then(|locations| write_locations(&locations, Format::Ron))
+                .map(|locations_ron| set_locations_input(self, locations_ron)),
+            FileAction::Save(path) => ron::de::from_str::<Vec<Location>>(&self.locations_ron)
+                .map_err(|error| SimulationError::InvalidInput(format!("RON: {}", error)))
+                .and_then(|locations| files::write_locations_file(path, &locations)),
+            FileAction::ExportRoute(path) => files::write_route_file(path, &self.current_route()),
+            FileAction::ExportImage(path) => {
+                let options = RenderOptions {
+                    title: self.files.current.as_ref().map(|current| {
+                        current
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .into_owned()
+                    }),
+                    ..RenderOptions::default()
+                };
+                files::write_image_file(path, &self.current_route(), &options)
+            }
+        };
+        match (result, action) {
+            (Ok(()), FileAction::Open(path) | FileAction::Save(path)) => {
+                self.simulation_error = None;
+                self.files.set_current(&path);
+            }
+            (Ok(()), FileAction::ExportRoute(_) | FileAction::ExportImage(_)) => {
+                self.simulation_error = None
+            }
+            (Err(error), _) => self.simulation_error = Some(error),
+        }
+    }
+}
+
+impl App {
+    // Applies an edit made on the canvas to the input locations, and rewrites their RON
+    fn edit_locations(&mut self, edit: CanvasEdit) {
+        let Ok(mut input) = locations_editor::parse_locations(&self.locations_ron) else {
+            return;
+        };
+        // the drawn locations are validated, so they may be in route order or renamed; they are
+        // matched with the input by their validated name
+        let input_index = |name: &str| {
+            self.locations_input_indices
+                .get(name)
+                .copied()
+                .filter(|index| *index < input.len())
+        };
+
+        match edit {
+            CanvasEdit::Add(x, y) => {
+                let name = (input.len() + 1..)
+                    .map(|number| number.to_string())
+                    .find(|name| input.iter().all(|location| &location.name != name))
+                    .unwrap_or_default();
+                input.push(Location { name, x, y });
+            }
+            CanvasEdit::Move(name, x, y) => {
+                let Some(index) = input_index(&name) else {
+                    return;
+                };
+                input[index].x = x;
+                input[index].y = y;
+            }
+            CanvasEdit::Delete(name) => {
+                let Some(index) = input_index(&name) else {
+                    return;
+                };
+                input.remove(index);
+            }
+            CanvasEdit::Rename(name, new_name) => {
+                let Some(index) = input_index(&name) else {
+                    return;
+                };
+                input[index].name = new_name;
+            }
         }
+        set_locations_input(self, locations_editor::locations_ron(&input));
     }
 }
 
@@ -118,13 +316,27 @@ fn locations_names(locations: &[Location]) -> Vec<String> {
 
 fn set_locations_input(app: &mut App, new_locations_ron: String) {
     app.locations_ron = new_locations_ron;
-    let _ = ron::de::from_str::<Vec<Location>>(&app.locations_ron)
-        .map(|locations| app.locations = locations);
+    match locations_editor::parse_locations(&app.locations_ron) {
+        Ok(locations) => {
+            let validated = validate_locations(&locations, app.duplicate_policy);
+            app.locations_input_indices = locations_names(&validated.locations)
+                .into_iter()
+                .zip(validated.input_indices)
+                .collect();
+            app.locations = validated.locations;
+            app.locations_diagnostics = validated.diagnostics;
+            app.locations_parse_error = None;
+        }
+        Err(parse_error) => app.locations_parse_error = Some(parse_error),
+    }
 
     app.route = locations_names(&app.locations);
     app.route_distance = f64::NAN;
     app.route_iteration = 0;
     app.total_iterations = 0;
+    app.total_evaluations = 0;
+    app.total_migrations = 0;
+    app.timeline.clear();
 }
 
 // Simulation
@@ -132,16 +344,20 @@ fn set_locations_input(app: &mut App, new_locations_ron: String) {
 #[derive(Debug)]
 enum SimulationCommand {
     Start(ParallelSimulation),
+    StartPortfolio(Portfolio<GeneticSimulation>),
+    Pause,
+    Resume,
     Stop,
 }
 
 fn simulation_control_loop(
     rx: Receiver<SimulationCommand>,
     tx: Sender<SimulationEvent>,
+    error_tx: Sender<SimulationError>,
     egui_ctx: egui::Context,
 ) {
     let started = Arc::new(AtomicBool::new(false));
-    let stop = Arc::new(AtomicBool::new(false));
+    let control = SimulationControl::new();
     loop {
         let command = rx.recv();
         match command {
@@ -151,92 +367,264 @@ fn simulation_control_loop(
                     started.compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed)
                 {
                     if !previous_value {
-                        start_simulation_thread(&tx, &started, &stop, simulation, egui_ctx.clone());
+                        start_simulation_thread(
+                            &tx,
+                            &error_tx,
+                            &started,
+                            &control,
+                            simulation,
+                            egui_ctx.clone(),
+                        );
                     }
                 }
             }
-            Ok(SimulationCommand::Stop) => stop.store(true, Ordering::Relaxed),
+            Ok(SimulationCommand::StartPortfolio(portfolio)) => {
+                if let Ok(previous_value) =
+                    started.compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed)
+                {
+                    if !previous_value {
+                        start_simulation_thread(
+                            &tx,
+                            &error_tx,
+                            &started,
+                            &control,
+                            portfolio,
+                            egui_ctx.clone(),
+                        );
+                    }
+                }
+            }
+            Ok(SimulationCommand::Pause) => control.pause(),
+            Ok(SimulationCommand::Resume) => control.resume(),
+            Ok(SimulationCommand::Stop) => control.stop(),
             _ => {}
         }
     }
 }
 
-fn start_simulation_thread(
+fn start_simulation_thread<S>(
     tx: &Sender<SimulationEvent>,
+    error_tx: &Sender<SimulationError>,
     started: &Arc<AtomicBool>,
-    stop: &Arc<AtomicBool>,
-    simulation: ParallelSimulation,
+    control: &SimulationControl,
+    simulation: S,
     egui_ctx: egui::Context,
-) {
+) where
+    S: Simulation + Send + 'static,
+{
     let tx2 = tx.clone();
+    let error_tx2 = error_tx.clone();
     let started2 = started.clone();
-    let stop2 = stop.clone();
+    let control2 = control.clone();
     thread::spawn(move || {
         println!("...started simulation thread");
-        simulation.run(&stop2, |event| {
+        let result = simulation.run(&control2, |event| {
             tx2.send(event).unwrap();
             egui_ctx.request_repaint();
         });
+        if let Err(error) = result {
+            println!("...simulation failed: {}", error);
+            let _ = error_tx2.send(error);
+            egui_ctx.request_repaint();
+        }
         println!("...simulation thread is done");
         started2.store(false, Ordering::Relaxed);
-        stop2.store(false, Ordering::Relaxed);
+        control2.reset();
     });
 }
 
 // GUI
 
 impl eframe::App for App {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, files::RECENT_FILES_KEY, &self.files.recent());
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Check for events from the simulation thread
-        let simulation_event = self.event_receiver.try_recv().ok();
-
-        match simulation_event {
-            Some(SimulationEvent::Iteration(iteration)) => {
-                self.total_iterations = iteration;
-                self.throughput_counter.update(self.total_iterations);
-            }
-            Some(SimulationEvent::NewChampion(route, iteration)) => {
-                self.route = locations_names(&route.locations);
-                self.locations = route.locations;
-                self.route_distance = route.distance;
-                self.route_iteration = iteration;
-            }
-            Some(SimulationEvent::Started) => {
-                self.simulation_running = true;
-                self.throughput_counter.start();
+        while let Ok(simulation_event) = self.event_receiver.try_recv() {
+            match simulation_event {
+                SimulationEvent::Iteration(iteration) => {
+                    self.total_iterations = iteration;
+                    self.throughput_counter.update(self.total_iterations);
+                }
+                SimulationEvent::Evaluations(evaluations) => {
+                    self.total_evaluations = evaluations;
+                }
+                SimulationEvent::NewChampion(route, iteration) => {
+                    self.convergence_plot
+                        .record_champion(route.distance, iteration);
+                    self.timeline.record(route.clone(), iteration);
+                    self.route = locations_names(&route.locations);
+                    self.locations = route.locations;
+                    self.route_distance = route.distance;
+                    self.route_iteration = iteration;
+                }
+                SimulationEvent::MeanDistance(mean_distance, iteration) => {
+                    self.convergence_plot.record_mean(mean_distance, iteration);
+                }
+                SimulationEvent::Migration(_, _, _) => self.total_migrations += 1,
+                SimulationEvent::WorkerFailed(worker, message) => self
+                    .worker_failures
+                    .push(format!("Thread {} failed: {}", worker, message)),
+                SimulationEvent::Leaderboard(standings) => self.leaderboard = standings,
+                SimulationEvent::WorkerStatistics(statistics) => {
+                    self.worker_statistics = statistics
+                }
+                SimulationEvent::Checkpoint(checkpoint) => {
+                    self.checkpoint_input.save(checkpoint, ctx)
+                }
+                SimulationEvent::StopConditionMet(_) => {}
+                SimulationEvent::Started => {
+                    self.simulation_running = true;
+                    self.simulation_paused = false;
+                    self.throughput_counter.start();
+                    self.convergence_plot.start_run();
+                    self.timeline.start_run();
+                    self.total_migrations = 0;
+                    self.worker_failures.clear();
+                    self.leaderboard.clear();
+                    self.worker_statistics.clear();
+                }
+                SimulationEvent::Finished => {
+                    self.simulation_running = false;
+                    self.simulation_paused = false;
+                }
             }
-            Some(SimulationEvent::Finished) => self.simulation_running = false,
-            _ => {}
+        }
+        if let Some(error) = self.checkpoint_input.save_error() {
+            self.simulation_error = Some(error);
+        }
+        // sent after the last event of the failed run
+        while let Ok(error) = self.error_receiver.try_recv() {
+            self.simulation_error = Some(error);
+            self.simulation_running = false;
+            self.simulation_paused = false;
+        }
+
+        // files dropped onto the window are opened
+        let dropped_file = ctx
+            .input()
+            .raw
+            .dropped_files
+            .iter()
+            .find_map(|dropped_file| dropped_file.path.clone());
+        if let Some(path) = dropped_file.filter(|_| !self.simulation_running) {
+            self.perform_file_action(FileAction::Open(path));
+        }
+        if let Some(action) = self.files.dialog_ui(ctx) {
+            self.perform_file_action(action);
+        }
+        if let Some(locations) = self.generator_dialog.ui(ctx, self.simulation_running) {
+            set_locations_input(self, locations_editor::locations_ron(&locations));
+            self.files.current = None;
+            self.route_canvas.reset_view();
         }
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
+                let route_available = !self.route_distance.is_nan();
+                let file_action = ui
+                    .menu_button("File", |ui| {
+                        self.files
+                            .menu_ui(ui, self.simulation_running, route_available)
+                    })
+                    .inner
+                    .flatten();
+                if let Some(action) = file_action {
+                    self.perform_file_action(action);
+                }
+                if let Some(path) = &self.files.current {
+                    ui.label(path.display().to_string());
+                }
+                ui.separator();
                 ui.label(format!("Distance: {:.3}", self.route_distance));
                 ui.separator();
                 ui.label(format!("Iterations: {:06}", self.total_iterations));
+                if self.simulation_paused {
+                    ui.label("(paused)");
+                }
+                ui.separator();
+                ui.label(format!("Evaluations: {}", self.total_evaluations));
+                if self.island_model_enabled {
+                    ui.separator();
+                    ui.label(format!("Migrations: {}", self.total_migrations));
+                }
                 ui.separator();
                 ui.add_enabled_ui(!self.simulation_running, |ui| {
                     ui.horizontal(|ui| {
                         ui.label("Examples:");
                         if ui.small_button("1").clicked() {
                             set_locations_input(self, examples::EXAMPLE1_RON.to_string());
+                            self.files.current = None;
                         }
                         if ui.small_button("2").clicked() {
                             set_locations_input(self, examples::EXAMPLE2_RON.to_string());
+                            self.files.current = None;
                         }
                         if ui.small_button("3").clicked() {
                             set_locations_input(self, examples::EXAMPLE3_RON.to_string());
+                            self.files.current = None;
+                        }
+                        if ui.small_button("Generate...").clicked() {
+                            self.generator_dialog.open();
                         }
                     });
                 });
+                ui.separator();
+                ui.checkbox(&mut self.show_convergence_plot, "Plot");
+                ui.checkbox(&mut self.show_worker_table, "Threads");
+                ui.checkbox(&mut self.show_route_list, "Route");
+                ui.checkbox(&mut self.show_timeline, "Timeline");
             });
         });
 
         egui::SidePanel::left("left_panel").show(ctx, |ui| {
             ui.add_enabled_ui(!self.simulation_running, |ui| {
-                if ui.text_edit_multiline(&mut self.locations_ron).changed() {
+                if locations_editor::locations_editor_ui(
+                    ui,
+                    &mut self.locations_ron,
+                    self.locations_parse_error.as_ref(),
+                )
+                .changed()
+                {
                     set_locations_input(self, self.locations_ron.to_owned());
                 }
+                if let Some(parse_error) = &self.locations_parse_error {
+                    locations_editor::parse_error_ui(ui, parse_error);
+                }
+
+                let duplicate_policy = self.duplicate_policy;
+                egui::ComboBox::from_label("Duplicates")
+                    .selected_text(format!("{:?}", self.duplicate_policy))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(
+                            &mut self.duplicate_policy,
+                            DuplicatePolicy::Reject,
+                            "Reject",
+                        );
+                        ui.selectable_value(
+                            &mut self.duplicate_policy,
+                            DuplicatePolicy::Merge,
+                            "Merge",
+                        );
+                        ui.selectable_value(
+                            &mut self.duplicate_policy,
+                            DuplicatePolicy::Rename,
+                            "Rename",
+                        );
+                    });
+                if self.duplicate_policy != duplicate_policy {
+                    set_locations_input(self, self.locations_ron.to_owned());
+                }
+
+                for diagnostic in &self.locations_diagnostics {
+                    let color = match diagnostic.severity {
+                        Severity::Warning => Color32::YELLOW,
+                        Severity::Error => Color32::RED,
+                    };
+                    ui.colored_label(color, diagnostic.to_string());
+                }
                 ui.separator();
 
                 ui.label("Population");
@@ -253,6 +641,73 @@ impl eframe::App for App {
                     }
                 }
                 ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Threads");
+                    ui.add(
+                        egui::DragValue::new(&mut self.threads).clamp_range(1..=self.max_threads),
+                    );
+                });
+                ui.checkbox(&mut self.pin_threads, "Pin threads to cores");
+                ui.horizontal(|ui| {
+                    ui.label("Restart failed threads");
+                    ui.add(egui::DragValue::new(&mut self.worker_restarts).clamp_range(0..=10))
+                        .on_hover_text(
+                            "Times a thread is restarted from the champion after a panic",
+                        );
+                });
+                ui.separator();
+
+                ui.checkbox(&mut self.island_model_enabled, "Island model");
+                ui.add_enabled_ui(self.island_model_enabled, |ui| {
+                    egui::ComboBox::from_label("Topology")
+                        .selected_text(format!("{:?}", self.island_model.topology))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.island_model.topology,
+                                MigrationTopology::Ring,
+                                "Ring",
+                            );
+                            ui.selectable_value(
+                                &mut self.island_model.topology,
+                                MigrationTopology::FullyConnected,
+                                "FullyConnected",
+                            );
+                            ui.selectable_value(
+                                &mut self.island_model.topology,
+                                MigrationTopology::Random,
+                                "Random",
+                            );
+                        });
+                    ui.horizontal(|ui| {
+                        ui.label("Migrate every");
+                        ui.add(
+                            egui::DragValue::new(&mut self.island_model.interval)
+                                .clamp_range(1..=usize::MAX)
+                                .suffix(" iterations"),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Migration size");
+                        ui.add(
+                            egui::DragValue::new(&mut self.island_model.size)
+                                .clamp_range(1..=7)
+                                .suffix(" routes"),
+                        );
+                    });
+                });
+                ui.separator();
+
+                self.stop_condition_input.ui(ui);
+                ui.separator();
+
+                self.portfolio_input.ui(ui);
+                ui.separator();
+
+                ui.add_enabled_ui(!self.portfolio_input.enabled, |ui| {
+                    self.checkpoint_input.ui(ui);
+                });
+                ui.separator();
             });
 
             let simulation_control_button_text = if !self.simulation_running {
@@ -260,68 +715,165 @@ impl eframe::App for App {
             } else {
                 "STOP"
             };
-            if ui.button(simulation_control_button_text).clicked() {
-                if !self.simulation_running {
-                    self.command_sender
-                        .send(SimulationCommand::Start(ParallelSimulation {
-                            population_size: self.population,
-                            ..ParallelSimulation::new(self.locations.clone())
-                        }))
-                        .unwrap();
+            let locations_valid = self.locations_parse_error.is_none()
+                && !self
+                    .locations_diagnostics
+                    .iter()
+                    .any(|diagnostic| diagnostic.severity == Severity::Error);
+            if ui
+                .add_enabled(
+                    self.simulation_running || locations_valid,
+                    egui::Button::new(simulation_control_button_text),
+                )
+                .clicked()
+            {
+                if !self.simulation_running && self.portfolio_input.enabled {
+                    let portfolio =
+                        self.stop_condition_input
+                            .stop_condition()
+                            .and_then(|stop_condition| {
+                                self.portfolio_input.portfolio(
+                                    &self.locations,
+                                    self.threads,
+                                    stop_condition,
+                                )
+                            });
+                    match portfolio {
+                        Ok(portfolio) => {
+                            self.simulation_error = None;
+                            self.command_sender
+                                .send(SimulationCommand::StartPortfolio(portfolio))
+                                .unwrap();
+                        }
+                        Err(error) => self.simulation_error = Some(error),
+                    }
+                } else if !self.simulation_running {
+                    let stop_condition = self.stop_condition_input.stop_condition();
+                    let simulation = stop_condition.and_then(|stop_condition| {
+                        let checkpoint = self.checkpoint_input.checkpoint()?;
+                        // the default iteration limits would cut the stop condition short
+                        let defaults = ParallelSimulation::new(vec![]);
+                        let limited = stop_condition.is_none();
+                        ParallelSimulation::builder(self.locations.clone())
+                            .population_size(self.population)
+                            .max_iterations(defaults.max_iterations.filter(|_| limited))
+                            .assume_convergence(defaults.assume_convergence.filter(|_| limited))
+                            .threads(self.threads)
+                            .pin_threads(self.pin_threads)
+                            .worker_restarts(self.worker_restarts)
+                            .island_model(
+                                self.island_model_enabled.then(|| self.island_model.clone()),
+                            )
+                            .stop_condition(stop_condition)
+                            .seed(self.checkpoint_input.seed())
+                            .checkpoint_interval(self.checkpoint_input.checkpoint_interval())
+                            .checkpoint(checkpoint)
+                            .build()
+                    });
+                    match simulation {
+                        Ok(simulation) => {
+                            self.simulation_error = None;
+                            self.command_sender
+                                .send(SimulationCommand::Start(simulation))
+                                .unwrap();
+                        }
+                        Err(error) => self.simulation_error = Some(error),
+                    }
                 } else {
                     self.command_sender.send(SimulationCommand::Stop).unwrap();
                 }
             }
-        });
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.with_layout(
-                egui::Layout::right_to_left(eframe::emath::Align::Min),
-                |ui| {
-                    ui.label(format!("Iteration: {:06}", self.route_iteration));
-                },
-            );
-
-            ui.with_layout(
-                egui::Layout::right_to_left(eframe::emath::Align::Min),
-                |ui| {
-                    ui.label(format!(
-                        "Throughput: {:06}",
-                        self.throughput_counter.throughput
-                    ));
-                },
-            );
-
-            let max_rect = ui.max_rect();
-            let max_left_top = max_rect.left_top();
-            let x_zero = max_left_top.x + 7.;
-            let y_zero = max_left_top.y + 7.;
-            let point_radius: f32 = 5.;
-            let point_color = Color32::LIGHT_RED;
-            let line_color = Color32::LIGHT_RED;
-
-            let painter = ui.painter().with_clip_rect(ui.max_rect());
-
-            let draw_point = |x: f32, y: f32| {
-                painter.circle_filled(pos2(x_zero + x, y_zero + y), point_radius, point_color);
-            };
+            // pausing keeps the population, so that the route can be inspected mid-run
+            if self.simulation_running {
+                let pause_button_text = if self.simulation_paused {
+                    "RESUME"
+                } else {
+                    "PAUSE"
+                };
+                if ui.button(pause_button_text).clicked() {
+                    let command = if self.simulation_paused {
+                        SimulationCommand::Resume
+                    } else {
+                        SimulationCommand::Pause
+                    };
+                    self.command_sender.send(command).unwrap();
+                    self.simulation_paused = !self.simulation_paused;
+                }
+            }
 
-            let draw_line = |x1: f32, y1: f32, x2: f32, y2: f32| {
-                let from = pos2(x_zero + x1, y_zero + y1);
-                let to = pos2(x_zero + x2, y_zero + y2);
-                painter.line_segment([from, to], Stroke::new(1., line_color))
-            };
+            if let Some(error) = &self.simulation_error {
+                ui.colored_label(Color32::RED, error.to_string());
+            }
+            for failure in &self.worker_failures {
+                ui.colored_label(Color32::RED, failure);
+            }
 
-            for location in &self.locations {
-                draw_point(location.x as f32, location.y as f32);
+            if !self.leaderboard.is_empty() {
+                ui.separator();
+                portfolio_input::leaderboard_ui(ui, &self.leaderboard);
             }
+        });
+
+        if self.show_convergence_plot {
+            egui::TopBottomPanel::bottom("plot_panel")
+                .resizable(true)
+                .default_height(200.)
+                .show(ctx, |ui| {
+                    self.convergence_plot.ui(ui);
+                });
+        }
+
+        if self.show_timeline {
+            egui::TopBottomPanel::bottom("timeline_panel").show(ctx, |ui| {
+                self.timeline.ui(ui);
+            });
+        }
+
+        if self.show_worker_table {
+            egui::TopBottomPanel::bottom("worker_panel")
+                .resizable(true)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        worker_table::worker_table_ui(ui, &self.worker_statistics);
+                    });
+                });
+        }
+
+        if self.show_route_list {
+            egui::SidePanel::right("route_panel").show(ctx, |ui| {
+                ui.label(format!("Route ({} locations)", self.route.len()));
+                ui.separator();
+                self.route_canvas.route_list_ui(ui, &self.route);
+            });
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                self.route_canvas.controls_ui(ui);
+                ui.with_layout(
+                    egui::Layout::right_to_left(eframe::emath::Align::Center),
+                    |ui| {
+                        ui.label(format!(
+                            "Throughput: {:06}",
+                            self.throughput_counter.throughput
+                        ));
+                        ui.label(format!("Iteration: {:06}", self.route_iteration));
+                    },
+                );
+            });
 
-            for (from, to) in (0..self.locations.len()).tuple_windows() {
-                let from_x = self.locations[from].x as f32;
-                let from_y = self.locations[from].y as f32;
-                let to_x = self.locations[to].x as f32;
-                let to_y = self.locations[to].y as f32;
-                draw_line(from_x, from_y, to_x, to_y);
+            // the champion route takes the colour of the thread that found it
+            let line_color = worker_table::champion_worker(&self.worker_statistics)
+                .map_or(Color32::LIGHT_RED, worker_table::worker_color);
+            // a past champion is drawn while going through the timeline
+            let displayed = self.timeline.displayed();
+            let locations = displayed.map_or(&self.locations, |snapshot| &snapshot.route.locations);
+            let editable = !self.simulation_running
+                && self.locations_parse_error.is_none()
+                && displayed.is_none();
+            if let Some(edit) = self.route_canvas.ui(ui, locations, line_color, editable) {
+                self.edit_locations(edit);
             }
         });
     }
@@ -0,0 +1,137 @@
+use eframe::egui;
+use eframe::egui::DragValue;
+use eframe::epaint::Color32;
+
+use std::str::FromStr;
+use std::time::Duration;
+use tsp_sim_agent::{
+    GeneticSimulation, Location, PortfolioStanding, SimulationError, StopCondition,
+};
+use tsp_sim_agent_parallel::{Portfolio, PortfolioConfiguration};
+
+// Side panel inputs used to race genetic simulations with different population sizes
+pub struct PortfolioInput {
+    pub enabled: bool,
+    populations_text: String,
+    race_interval_seconds: f64,
+    elimination_margin_percent: f64,
+}
+
+impl PortfolioInput {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            populations_text: "50, 200, 1000".to_string(),
+            race_interval_seconds: 5.0,
+            elimination_margin_percent: 5.0,
+        }
+    }
+
+    fn populations(&self) -> Vec<usize> {
+        self.populations_text
+            .split(',')
+            .filter_map(|population| usize::from_str(population.trim()).ok())
+            .collect()
+    }
+
+    // One configuration per population size, sharing the threads
+    pub fn portfolio(
+        &self,
+        locations: &[Location],
+        threads: usize,
+        stop_condition: Option<StopCondition>,
+    ) -> Result<Portfolio<GeneticSimulation>, SimulationError> {
+        let populations = self.populations();
+        let configuration_threads = (threads / populations.len().max(1)).max(1);
+        // the default iteration limits would cut the stop condition short
+        let defaults = GeneticSimulation::new(vec![]);
+        let limited = stop_condition.is_none();
+        let configurations = populations
+            .into_iter()
+            .map(|population| {
+                let simulation = GeneticSimulation::builder(locations.to_vec())
+                    .population_size(population)
+                    .max_iterations(defaults.max_iterations.filter(|_| limited))
+                    .assume_convergence(defaults.assume_convergence.filter(|_| limited))
+                    .build()?;
+                Ok(PortfolioConfiguration::new(
+                    &format!("Population {}", population),
+                    configuration_threads,
+                    move |_, initial_routes| GeneticSimulation {
+                        population_size: simulation.population_size,
+                        max_iterations: simulation.max_iterations,
+                        assume_convergence: simulation.assume_convergence,
+                        initial_routes,
+                        ..GeneticSimulation::new(simulation.locations.clone())
+                    },
+                ))
+            })
+            .collect::<Result<Vec<_>, SimulationError>>()?;
+
+        let portfolio = Portfolio {
+            race_interval: Duration::from_secs_f64(self.race_interval_seconds),
+            elimination_margin: self.elimination_margin_percent / 100.0,
+            stop_condition,
+            ..Portfolio::new(configurations)
+        };
+        portfolio.validate()?;
+        Ok(portfolio)
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.enabled, "Portfolio racing");
+        ui.add_enabled_ui(self.enabled, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Populations");
+                ui.text_edit_singleline(&mut self.populations_text)
+                    .on_hover_text("Comma separated population sizes, one configuration each");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Race every");
+                ui.add(
+                    DragValue::new(&mut self.race_interval_seconds)
+                        .clamp_range(0.1..=3600.0)
+                        .speed(0.1)
+                        .suffix(" s"),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Eliminate when behind by");
+                ui.add(
+                    DragValue::new(&mut self.elimination_margin_percent)
+                        .clamp_range(0.0..=100.0)
+                        .speed(0.1)
+                        .suffix(" %"),
+                );
+            });
+        });
+    }
+}
+
+pub fn leaderboard_ui(ui: &mut egui::Ui, standings: &[PortfolioStanding]) {
+    egui::Grid::new("leaderboard").striped(true).show(ui, |ui| {
+        ui.label("Configuration");
+        ui.label("Distance");
+        ui.label("Iterations");
+        ui.label("Threads");
+        ui.end_row();
+
+        for standing in standings {
+            let color = if standing.eliminated {
+                Color32::GRAY
+            } else {
+                ui.visuals().text_color()
+            };
+            ui.colored_label(color, &standing.name);
+            ui.colored_label(
+                color,
+                standing
+                    .champion_distance
+                    .map_or("-".to_string(), |distance| format!("{:.3}", distance)),
+            );
+            ui.colored_label(color, standing.iterations.to_string());
+            ui.colored_label(color, standing.threads.to_string());
+            ui.end_row();
+        }
+    });
+}
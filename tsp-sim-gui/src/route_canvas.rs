@@ -0,0 +1,456 @@
+use eframe::egui;
+use eframe::emath::{pos2, vec2, Align2, Pos2, Rect, Vec2};
+use eframe::epaint::{Color32, FontId, Stroke};
+use itertools::Itertools;
+
+use tsp_sim_agent::Location;
+
+const POINT_RADIUS: f32 = 5.;
+const POINT_COLOR: Color32 = Color32::LIGHT_RED;
+const START_COLOR: Color32 = Color32::LIGHT_GREEN;
+const END_COLOR: Color32 = Color32::LIGHT_BLUE;
+const HIGHLIGHT_COLOR: Color32 = Color32::YELLOW;
+// Radius of the start, end and highlighted locations
+const MARKED_POINT_RADIUS: f32 = 7.;
+// Length of the arrows drawn in the middle of the route edges, in pixels
+const ARROW_LENGTH: f32 = 16.;
+// Distance from a location, in pixels, within which the pointer picks it
+const PICK_RADIUS: f32 = POINT_RADIUS + 3.;
+// Space kept between the fitted locations and the canvas border, in pixels
+const FIT_MARGIN: f32 = 20.;
+const MIN_ZOOM: f32 = 0.01;
+const MAX_ZOOM: f32 = 1000.;
+// Pixels of wheel scrolling that zoom in by a factor of e
+const SCROLL_ZOOM_SPEED: f32 = 200.;
+// Minimum distance between grid lines, in pixels
+const MIN_GRID_SPACING: f32 = 50.;
+const GRID_COLOR: Color32 = Color32::from_gray(50);
+const AXES_COLOR: Color32 = Color32::from_gray(120);
+
+// Maps location coordinates to canvas positions; y grows downwards on both
+#[derive(Clone, Copy)]
+pub struct Transform {
+    world_center: Pos2,
+    screen_center: Pos2,
+    scale: f32,
+}
+
+impl Transform {
+    pub fn to_screen(self, x: f64, y: f64) -> Pos2 {
+        self.screen_center + (pos2(x as f32, y as f32) - self.world_center) * self.scale
+    }
+
+    // Coordinates at the position, rounded to the precision of a pixel
+    pub fn to_world(self, position: Pos2) -> (f64, f64) {
+        let world = self.world_center + (position - self.screen_center) / self.scale;
+        let decimals = (self.scale as f64).log10().ceil().max(0.) as i32;
+        let round = |value: f32| {
+            let factor = 10f64.powi(decimals);
+            (value as f64 * factor).round() / factor
+        };
+        (round(world.x), round(world.y))
+    }
+}
+
+// Change of the input locations made on the canvas; locations are identified by their drawn name,
+// which stays the same when a moved location is drawn at another place in the route
+pub enum CanvasEdit {
+    Add(f64, f64),          // (x, y)
+    Move(String, f64, f64), // (name, x, y)
+    Delete(String),         // (name)
+    Rename(String, String), // (name, new name)
+}
+
+// Draws the locations and route, scaled to fit the canvas, with wheel zoom and drag panning;
+// when editable, locations are added by clicking, moved by dragging and renamed or deleted
+// from the right-click menu
+pub struct RouteCanvas {
+    zoom: f32,
+    pan: Vec2,
+    show_grid: bool,
+    show_axes: bool,
+    show_labels: bool,
+    show_order: bool,
+    show_arrows: bool,
+    show_endpoints: bool,
+    // location highlighted from the route list this frame, and the one highlighted last frame,
+    // from the list or the canvas
+    list_highlight: Option<usize>,
+    highlighted: Option<usize>,
+    highlighted_on_canvas: bool,
+    // location being dragged, and the transform kept while it moves so that the view does not
+    // refit under the pointer
+    dragged: Option<(String, Transform)>,
+    // location the right-click menu is open for, and its name being edited
+    context_location: Option<String>,
+    rename_text: String,
+}
+
+impl RouteCanvas {
+    pub fn new() -> Self {
+        Self {
+            zoom: 1.,
+            pan: Vec2::ZERO,
+            show_grid: false,
+            show_axes: false,
+            show_labels: false,
+            show_order: false,
+            show_arrows: false,
+            show_endpoints: true,
+            list_highlight: None,
+            highlighted: None,
+            highlighted_on_canvas: false,
+            dragged: None,
+            context_location: None,
+            rename_text: String::new(),
+        }
+    }
+
+    pub fn reset_view(&mut self) {
+        self.zoom = 1.;
+        self.pan = Vec2::ZERO;
+    }
+
+    pub fn controls_ui(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Reset view").clicked() {
+            self.reset_view();
+        }
+        ui.checkbox(&mut self.show_grid, "Grid");
+        ui.checkbox(&mut self.show_axes, "Axes");
+        ui.separator();
+        ui.checkbox(&mut self.show_labels, "Names");
+        ui.checkbox(&mut self.show_order, "Order");
+        ui.checkbox(&mut self.show_arrows, "Arrows");
+        ui.checkbox(&mut self.show_endpoints, "Start/end");
+    }
+
+    // Side list of the route order; hovering a location highlights it on the canvas, and
+    // hovering it on the canvas highlights it in the list
+    pub fn route_list_ui(&mut self, ui: &mut egui::Ui, route: &[String]) {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (index, name) in route.iter().enumerate() {
+                let label = ui.selectable_label(
+                    self.highlighted == Some(index),
+                    format!("{}. {}", index + 1, name),
+                );
+                if self.highlighted == Some(index) && self.highlighted_on_canvas {
+                    label.scroll_to_me(None);
+                }
+                if label.hovered() {
+                    self.list_highlight = Some(index);
+                }
+            }
+        });
+    }
+
+    // Transform fitting the locations in the rect, with the zoom and pan applied
+    fn transform(&self, rect: Rect, locations: &[Location]) -> Transform {
+        let bounds = bounds(locations);
+        let available = (rect.size() - Vec2::splat(2. * FIT_MARGIN)).max(Vec2::splat(1.));
+        let size = bounds.size().max(Vec2::splat(f32::EPSILON));
+        let fit_scale = (available.x / size.x).min(available.y / size.y);
+        // a single location, or several at the same point, is drawn at the current scale
+        let fit_scale = if fit_scale.is_finite() && bounds.size() != Vec2::ZERO {
+            fit_scale
+        } else {
+            1.
+        };
+        Transform {
+            world_center: bounds.center(),
+            screen_center: rect.center() + self.pan,
+            scale: fit_scale * self.zoom,
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        locations: &[Location],
+        line_color: Color32,
+        editable: bool,
+    ) -> Option<CanvasEdit> {
+        let (response, painter) =
+            ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+        let rect = response.rect;
+        let transform = match &self.dragged {
+            Some((_, transform)) => *transform,
+            None => self.transform(rect, locations),
+        };
+        let picked = |position: Option<Pos2>| {
+            position.and_then(|position| pick(locations, transform, position))
+        };
+        let mut edit = None;
+
+        if editable && response.drag_started() {
+            self.dragged = picked(response.interact_pointer_pos())
+                .map(|index| (locations[index].name.clone(), transform));
+        }
+        if response.dragged() {
+            match (&self.dragged, response.interact_pointer_pos()) {
+                (Some((name, _)), Some(pointer)) => {
+                    let (x, y) = transform.to_world(pointer);
+                    edit = Some(CanvasEdit::Move(name.clone(), x, y));
+                }
+                _ => self.pan += response.drag_delta(),
+            }
+        }
+        if response.drag_released() {
+            self.dragged = None;
+        }
+
+        if let Some(pointer) = response.hover_pos() {
+            let input = ui.input();
+            let factor = input.zoom_delta() * (input.scroll_delta.y / SCROLL_ZOOM_SPEED).exp();
+            let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+            if zoom != self.zoom {
+                // keeps the point under the pointer in place
+                let center = rect.center() + self.pan;
+                self.pan += (pointer - center) * (1. - zoom / self.zoom);
+                self.zoom = zoom;
+            }
+        }
+        let canvas_highlight = if self.dragged.is_none() {
+            picked(response.hover_pos())
+        } else {
+            None
+        };
+
+        if editable {
+            if response.clicked() && picked(response.interact_pointer_pos()).is_none() {
+                if let Some(pointer) = response.interact_pointer_pos() {
+                    let (x, y) = transform.to_world(pointer);
+                    edit = Some(CanvasEdit::Add(x, y));
+                }
+            }
+            if response.hovered() && picked(response.hover_pos()).is_some() {
+                ui.output().cursor_icon = egui::CursorIcon::Grab;
+            }
+            if response.secondary_clicked() {
+                self.context_location = picked(response.interact_pointer_pos())
+                    .map(|index| locations[index].name.clone());
+                self.rename_text = self.context_location.clone().unwrap_or_default();
+            }
+            let response = response.on_hover_text(
+                "Click to add a location, drag to move it, right-click to rename or delete it",
+            );
+            response.context_menu(|ui| {
+                if let Some(menu_edit) = self.context_menu_ui(ui) {
+                    edit = Some(menu_edit);
+                    ui.close_menu();
+                }
+            });
+        }
+
+        let list_highlight = self.list_highlight.take();
+        self.highlighted = list_highlight.or(canvas_highlight);
+        self.highlighted_on_canvas = list_highlight.is_none() && canvas_highlight.is_some();
+
+        let painter = painter.with_clip_rect(rect);
+
+        if self.show_grid || self.show_axes {
+            draw_grid(&painter, rect, transform, self.show_grid, self.show_axes);
+        }
+
+        let line_stroke = Stroke::new(1., line_color);
+        for (from, to) in locations.iter().tuple_windows() {
+            let from = transform.to_screen(from.x, from.y);
+            let to = transform.to_screen(to.x, to.y);
+            painter.line_segment([from, to], line_stroke);
+            if self.show_arrows && from.distance(to) > ARROW_LENGTH {
+                let direction = (to - from).normalized() * ARROW_LENGTH;
+                painter.arrow(from + (to - from - direction) / 2., direction, line_stroke);
+            }
+        }
+
+        for location in locations {
+            painter.circle_filled(
+                transform.to_screen(location.x, location.y),
+                POINT_RADIUS,
+                POINT_COLOR,
+            );
+        }
+
+        let mut marked = vec![];
+        if self.show_endpoints && !locations.is_empty() {
+            marked.push((0, START_COLOR));
+            marked.push((locations.len() - 1, END_COLOR));
+        }
+        if let Some(index) = self.highlighted.filter(|index| *index < locations.len()) {
+            marked.push((index, HIGHLIGHT_COLOR));
+        }
+        for (index, color) in marked {
+            let location = &locations[index];
+            painter.circle_filled(
+                transform.to_screen(location.x, location.y),
+                MARKED_POINT_RADIUS,
+                color,
+            );
+        }
+
+        if self.show_labels || self.show_order {
+            let font_id = FontId::proportional(12.);
+            let text_color = ui.visuals().text_color();
+            for (index, location) in locations.iter().enumerate() {
+                let position = transform.to_screen(location.x, location.y);
+                if self.show_labels {
+                    painter.text(
+                        position + vec2(MARKED_POINT_RADIUS, -MARKED_POINT_RADIUS),
+                        Align2::LEFT_BOTTOM,
+                        &location.name,
+                        font_id.clone(),
+                        text_color,
+                    );
+                }
+                if self.show_order {
+                    painter.text(
+                        position + vec2(-MARKED_POINT_RADIUS, MARKED_POINT_RADIUS),
+                        Align2::RIGHT_TOP,
+                        (index + 1).to_string(),
+                        font_id.clone(),
+                        HIGHLIGHT_COLOR,
+                    );
+                }
+            }
+        }
+
+        edit
+    }
+
+    fn context_menu_ui(&mut self, ui: &mut egui::Ui) -> Option<CanvasEdit> {
+        let Some(location_name) = self.context_location.clone() else {
+            ui.label("Right-click a location to edit it");
+            return None;
+        };
+        let mut edit = None;
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            let name_input = ui.text_edit_singleline(&mut self.rename_text);
+            let name = self.rename_text.trim();
+            let submitted = name_input.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+            if (ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Rename"))
+                .clicked()
+                || submitted)
+                && !name.is_empty()
+            {
+                edit = Some(CanvasEdit::Rename(location_name.clone(), name.to_owned()));
+            }
+        });
+        if ui.button("Delete").clicked() {
+            edit = Some(CanvasEdit::Delete(location_name));
+        }
+        edit
+    }
+}
+
+// Index of the location drawn closest to the position, if within picking distance
+fn pick(locations: &[Location], transform: Transform, position: Pos2) -> Option<usize> {
+    locations
+        .iter()
+        .map(|location| {
+            transform
+                .to_screen(location.x, location.y)
+                .distance(position)
+        })
+        .enumerate()
+        .filter(|(_, distance)| *distance <= PICK_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+}
+
+fn bounds(locations: &[Location]) -> Rect {
+    if locations.is_empty() {
+        return Rect::from_min_max(Pos2::ZERO, pos2(1., 1.));
+    }
+    Rect::from_points(
+        &locations
+            .iter()
+            .map(|location| pos2(location.x as f32, location.y as f32))
+            .collect::<Vec<_>>(),
+    )
+}
+
+// Smallest 1, 2 or 5 times a power of ten that is at least the given world distance
+fn grid_step(min_step: f64) -> f64 {
+    let power = 10f64.powf(min_step.log10().floor());
+    [1., 2., 5., 10.]
+        .into_iter()
+        .map(|multiple| multiple * power)
+        .find(|step| *step >= min_step)
+        .unwrap_or(10. * power)
+}
+
+fn draw_grid(
+    painter: &egui::Painter,
+    rect: Rect,
+    transform: Transform,
+    show_grid: bool,
+    show_axes: bool,
+) {
+    let (left, top) = transform.to_world(rect.left_top());
+    let (right, bottom) = transform.to_world(rect.right_bottom());
+    let step = grid_step((MIN_GRID_SPACING / transform.scale) as f64);
+    let font_id = FontId::monospace(10.);
+
+    if show_grid {
+        let grid_stroke = Stroke::new(1., GRID_COLOR);
+        for index in (left / step).ceil() as i64..=(right / step).floor() as i64 {
+            let x = index as f64 * step;
+            let screen_x = transform.to_screen(x, 0.).x;
+            painter.vline(screen_x, rect.y_range(), grid_stroke);
+            painter.text(
+                pos2(screen_x + 2., rect.bottom() - 2.),
+                Align2::LEFT_BOTTOM,
+                format_coordinate(x, step),
+                font_id.clone(),
+                AXES_COLOR,
+            );
+        }
+        for index in (top / step).ceil() as i64..=(bottom / step).floor() as i64 {
+            let y = index as f64 * step;
+            let screen_y = transform.to_screen(0., y).y;
+            painter.hline(rect.x_range(), screen_y, grid_stroke);
+            painter.text(
+                pos2(rect.left() + 2., screen_y - 2.),
+                Align2::LEFT_BOTTOM,
+                format_coordinate(y, step),
+                font_id.clone(),
+                AXES_COLOR,
+            );
+        }
+    }
+
+    if show_axes {
+        let origin = transform.to_screen(0., 0.);
+        let axes_stroke = Stroke::new(1.5, AXES_COLOR);
+        painter.hline(rect.x_range(), origin.y, axes_stroke);
+        painter.vline(origin.x, rect.y_range(), axes_stroke);
+        painter.text(
+            origin + vec2(3., 2.),
+            Align2::LEFT_TOP,
+            "0",
+            font_id.clone(),
+            AXES_COLOR,
+        );
+        painter.text(
+            pos2(rect.right() - 3., origin.y - 2.),
+            Align2::RIGHT_BOTTOM,
+            "x",
+            font_id.clone(),
+            AXES_COLOR,
+        );
+        painter.text(
+            pos2(origin.x + 3., rect.bottom() - 3.),
+            Align2::LEFT_BOTTOM,
+            "y",
+            font_id,
+            AXES_COLOR,
+        );
+    }
+}
+
+// Coordinate label with as many decimals as the grid step needs
+fn format_coordinate(value: f64, step: f64) -> String {
+    let decimals = (-step.log10().floor()).max(0.) as usize;
+    format!("{:.*}", decimals, value)
+}
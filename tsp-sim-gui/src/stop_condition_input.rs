@@ -0,0 +1,117 @@
+use eframe::egui;
+use eframe::egui::DragValue;
+
+use std::time::Duration;
+use tsp_sim_agent::{SimulationError, StopCondition};
+
+// Side panel inputs used to compose the simulation stop condition
+pub struct StopConditionInput {
+    all: bool,
+    time_limit_enabled: bool,
+    time_limit_seconds: f64,
+    target_distance_enabled: bool,
+    target_distance: f64,
+    max_evaluations_enabled: bool,
+    max_evaluations: usize,
+    improvement_enabled: bool,
+    improvement_window: usize,
+    improvement_threshold_percent: f64,
+}
+
+impl StopConditionInput {
+    pub fn new() -> Self {
+        Self {
+            all: false,
+            time_limit_enabled: false,
+            time_limit_seconds: 60.0,
+            target_distance_enabled: false,
+            target_distance: 0.0,
+            max_evaluations_enabled: false,
+            max_evaluations: 10_000_000,
+            improvement_enabled: false,
+            improvement_window: 10_000,
+            improvement_threshold_percent: 0.1,
+        }
+    }
+
+    pub fn stop_condition(&self) -> Result<Option<StopCondition>, SimulationError> {
+        let mut conditions = vec![];
+        if self.time_limit_enabled {
+            let time_limit =
+                Duration::try_from_secs_f64(self.time_limit_seconds).map_err(|_| {
+                    SimulationError::InvalidStopCondition("time limit is too long".to_owned())
+                })?;
+            conditions.push(StopCondition::TimeLimit(time_limit));
+        }
+        if self.target_distance_enabled {
+            conditions.push(StopCondition::TargetDistance(self.target_distance));
+        }
+        if self.max_evaluations_enabled {
+            conditions.push(StopCondition::MaxEvaluations(self.max_evaluations));
+        }
+        if self.improvement_enabled {
+            conditions.push(StopCondition::ImprovementBelow {
+                window: self.improvement_window,
+                threshold: self.improvement_threshold_percent / 100.0,
+            });
+        }
+
+        Ok(match conditions.len() {
+            0 => None,
+            1 => conditions.pop(),
+            _ if self.all => Some(StopCondition::All(conditions)),
+            _ => Some(StopCondition::Any(conditions)),
+        })
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Stop when");
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.all, false, "any");
+            ui.radio_value(&mut self.all, true, "all");
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.time_limit_enabled, "Time limit");
+            ui.add_enabled(
+                self.time_limit_enabled,
+                DragValue::new(&mut self.time_limit_seconds)
+                    .clamp_range(0.0..=f64::MAX)
+                    .suffix(" s"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.target_distance_enabled, "Target distance");
+            ui.add_enabled(
+                self.target_distance_enabled,
+                DragValue::new(&mut self.target_distance).clamp_range(0.0..=f64::MAX),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.max_evaluations_enabled, "Max evaluations");
+            ui.add_enabled(
+                self.max_evaluations_enabled,
+                DragValue::new(&mut self.max_evaluations).speed(10_000),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.improvement_enabled, "Improvement below");
+            ui.add_enabled(
+                self.improvement_enabled,
+                DragValue::new(&mut self.improvement_threshold_percent)
+                    .clamp_range(0.0..=100.0)
+                    .speed(0.01)
+                    .suffix(" %"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("over");
+            ui.add_enabled(
+                self.improvement_enabled,
+                DragValue::new(&mut self.improvement_window)
+                    .speed(100)
+                    .suffix(" iterations"),
+            );
+        });
+    }
+}
@@ -0,0 +1,245 @@
+use eframe::egui;
+use eframe::egui::Slider;
+use eframe::epaint::Color32;
+
+use std::fs;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tsp_sim_agent::{render_gif, RenderOptions, Route, SimulationError};
+
+// Older champions are thinned out beyond this many, keeping the memory bounded on long runs
+const MAX_SNAPSHOTS: usize = 5000;
+const MAX_GIF_FRAMES: usize = 200;
+const GIF_WIDTH: u32 = 640;
+const GIF_HEIGHT: u32 = 480;
+
+pub struct ChampionSnapshot {
+    pub route: Route,
+    pub iteration: usize,
+    pub elapsed: Duration,
+}
+
+// Every champion of the current run, with a slider to go back to any of them, playback, and
+// export of the replay as an animated GIF
+pub struct Timeline {
+    history: Vec<ChampionSnapshot>,
+    start_time: Instant,
+    // snapshot shown instead of the latest champion, while scrubbing or playing
+    selected: Option<usize>,
+    playing: bool,
+    // champions shown per second, in the playback and the exported GIF
+    speed: f64,
+    // fraction of the time to the next snapshot that the playback has gone through
+    playback_progress: f64,
+    gif_path: String,
+    // result of the GIF export running in the background
+    export_receiver: Option<Receiver<Result<String, SimulationError>>>,
+    export_result: Option<Result<String, SimulationError>>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self {
+            history: vec![],
+            start_time: Instant::now(),
+            selected: None,
+            playing: false,
+            speed: 5.,
+            playback_progress: 0.,
+            gif_path: "replay.gif".to_string(),
+            export_receiver: None,
+            export_result: None,
+        }
+    }
+
+    pub fn start_run(&mut self) {
+        self.clear();
+        self.start_time = Instant::now();
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.selected = None;
+        self.playing = false;
+    }
+
+    pub fn record(&mut self, route: Route, iteration: usize) {
+        self.history.push(ChampionSnapshot {
+            route,
+            iteration,
+            elapsed: self.start_time.elapsed(),
+        });
+        if self.history.len() > MAX_SNAPSHOTS && self.selected.is_none() {
+            let last = self.history.len() - 1;
+            let mut index = 0;
+            self.history.retain(|_| {
+                index += 1;
+                index % 2 == 1 || index - 1 == last
+            });
+        }
+    }
+
+    // The champion to draw instead of the latest one
+    pub fn displayed(&self) -> Option<&ChampionSnapshot> {
+        self.selected.and_then(|index| self.history.get(index))
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        // the export goes on when the history is cleared by a new run
+        self.receive_export_result();
+
+        if self.history.is_empty() {
+            ui.label("The champions of the next run are recorded here.");
+            return;
+        }
+        let last = self.history.len() - 1;
+
+        if self.playing {
+            self.advance_playback(ui);
+        }
+
+        ui.horizontal(|ui| {
+            let play_text = if self.playing { "Pause" } else { "Play" };
+            if ui.button(play_text).clicked() {
+                self.playing = !self.playing;
+                if self.playing && self.selected.is_none_or(|index| index >= last) {
+                    self.selected = Some(0);
+                }
+                self.playback_progress = 0.;
+            }
+            if ui
+                .add_enabled(self.selected.is_some(), egui::Button::new("Latest"))
+                .on_hover_text("Follow the latest champion")
+                .clicked()
+            {
+                self.selected = None;
+                self.playing = false;
+            }
+
+            let mut index = self.selected.unwrap_or(last);
+            if ui.add(Slider::new(&mut index, 0..=last)).changed() {
+                self.selected = Some(index);
+                self.playing = false;
+            }
+            let snapshot = &self.history[index];
+            ui.label(format!(
+                "Champion {}/{}, iteration {}, {:.1} s, distance {:.3}",
+                index + 1,
+                last + 1,
+                snapshot.iteration,
+                snapshot.elapsed.as_secs_f64(),
+                snapshot.route.distance
+            ));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Speed");
+            ui.add(
+                Slider::new(&mut self.speed, 1.0..=60.0)
+                    .logarithmic(true)
+                    .suffix(" champions/s"),
+            );
+            ui.separator();
+            ui.label("GIF");
+            ui.text_edit_singleline(&mut self.gif_path);
+            let exporting = self.export_receiver.is_some();
+            if ui
+                .add_enabled(!exporting, egui::Button::new("Export"))
+                .on_hover_text(format!(
+                    "Replays at most {} champions, at the playback speed",
+                    MAX_GIF_FRAMES
+                ))
+                .clicked()
+            {
+                self.export_gif(ui.ctx().clone());
+            }
+            match &self.export_result {
+                _ if exporting => {
+                    ui.spinner();
+                    ui.label("Exporting...");
+                }
+                Some(Ok(message)) => {
+                    ui.label(message);
+                }
+                Some(Err(error)) => {
+                    ui.colored_label(Color32::RED, error.to_string());
+                }
+                None => {}
+            }
+        });
+    }
+
+    fn advance_playback(&mut self, ui: &egui::Ui) {
+        let last = self.history.len() - 1;
+        self.playback_progress += ui.input().stable_dt as f64 * self.speed;
+        let mut index = self.selected.unwrap_or(0);
+        while self.playback_progress >= 1. && index < last {
+            self.playback_progress -= 1.;
+            index += 1;
+        }
+        self.selected = Some(index);
+        if index >= last {
+            self.playing = false;
+        } else {
+            ui.ctx().request_repaint();
+        }
+    }
+
+    fn receive_export_result(&mut self) {
+        let Some(export_receiver) = &self.export_receiver else {
+            return;
+        };
+        let result = match export_receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err(SimulationError::Render(
+                "the export stopped unexpectedly".to_owned(),
+            )),
+        };
+        self.export_result = Some(result);
+        self.export_receiver = None;
+    }
+
+    // Renders the GIF on another thread, which can take a while for many frames
+    fn export_gif(&mut self, egui_ctx: egui::Context) {
+        // the champions are sampled evenly, always ending with the last one
+        let step = self.history.len().div_ceil(MAX_GIF_FRAMES);
+        let last = self.history.len() - 1;
+        let frames: Vec<(Route, RenderOptions)> = self
+            .history
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| index % step == 0 || *index == last)
+            .map(|(_, snapshot)| {
+                let options = RenderOptions {
+                    width: GIF_WIDTH,
+                    height: GIF_HEIGHT,
+                    labels: false,
+                    title: Some(format!("Iteration {}", snapshot.iteration)),
+                    stats: true,
+                };
+                (snapshot.route.clone(), options)
+            })
+            .collect();
+        let frame_delay = Duration::from_secs_f64(1. / self.speed);
+        let gif_path = self.gif_path.trim().to_owned();
+
+        let (export_sender, export_receiver) = mpsc::channel();
+        self.export_receiver = Some(export_receiver);
+        thread::spawn(move || {
+            let frames: Vec<(&Route, RenderOptions)> = frames
+                .iter()
+                .map(|(route, options)| (route, options.clone()))
+                .collect();
+            let result = render_gif(&frames, frame_delay).and_then(|gif| {
+                fs::write(&gif_path, gif).map_err(|error| {
+                    SimulationError::InvalidInput(format!("{}: {}", gif_path, error))
+                })?;
+                Ok(format!("Exported {} frames", frames.len()))
+            });
+            let _ = export_sender.send(result);
+            egui_ctx.request_repaint();
+        });
+    }
+}
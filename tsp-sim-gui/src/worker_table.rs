@@ -0,0 +1,67 @@
+use eframe::egui;
+use eframe::epaint::Color32;
+
+use tsp_sim_agent::WorkerStatistics;
+
+const WORKER_COLORS: [Color32; 8] = [
+    Color32::LIGHT_RED,
+    Color32::LIGHT_GREEN,
+    Color32::LIGHT_BLUE,
+    Color32::GOLD,
+    Color32::from_rgb(255, 160, 60),
+    Color32::from_rgb(200, 130, 255),
+    Color32::from_rgb(90, 220, 220),
+    Color32::from_rgb(255, 130, 200),
+];
+
+pub fn worker_color(worker: usize) -> Color32 {
+    WORKER_COLORS[worker % WORKER_COLORS.len()]
+}
+
+// Thread whose route is the current champion
+pub fn champion_worker(statistics: &[WorkerStatistics]) -> Option<usize> {
+    statistics
+        .iter()
+        .find(|worker_statistics| worker_statistics.holds_champion)
+        .map(|worker_statistics| worker_statistics.worker)
+}
+
+fn distance_text(distance: Option<f64>) -> String {
+    distance.map_or("-".to_string(), |distance| format!("{:.3}", distance))
+}
+
+pub fn worker_table_ui(ui: &mut egui::Ui, statistics: &[WorkerStatistics]) {
+    egui::Grid::new("worker_table")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Thread");
+            ui.label("Iterations");
+            ui.label("Evaluations");
+            ui.label("Best");
+            ui.label("Mean");
+            ui.label("Champions");
+            ui.label("Restarts");
+            ui.end_row();
+
+            for worker_statistics in statistics {
+                let thread_text = if worker_statistics.running {
+                    worker_statistics.worker.to_string()
+                } else {
+                    format!("{} (done)", worker_statistics.worker)
+                };
+                ui.colored_label(worker_color(worker_statistics.worker), thread_text);
+                ui.label(worker_statistics.iterations.to_string());
+                ui.label(worker_statistics.evaluations.to_string());
+                ui.label(distance_text(worker_statistics.best_distance));
+                ui.label(distance_text(worker_statistics.mean_distance));
+                let champions_text = if worker_statistics.holds_champion {
+                    format!("{} *", worker_statistics.champions)
+                } else {
+                    worker_statistics.champions.to_string()
+                };
+                ui.label(champions_text);
+                ui.label(worker_statistics.restarts.to_string());
+                ui.end_row();
+            }
+        });
+}